@@ -0,0 +1,144 @@
+// This module runs the benchmark of the two BIT resolutions (adjacency matrix with lists,
+// adjacency and predecessor hash maps) on random DAGs of increasing size, and writes the
+// timings, the comparison of their results and the integrals produced to four files.
+// Each file starts with the seed of the run; the DAG of each size is drawn from its own
+// generator (see `sub_seed`), so a single size can be rerun alone.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::generator_matrix::{random_seed, rng_from_seed, sub_seed, GraphKind};
+use crate::resolution_using_hashmap::Graph;
+use crate::resolution_using_lists::list_BIT_resolution;
+
+/// Options of a benchmark run.
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Smallest number of nodes
+    pub from: usize,
+    /// Largest number of nodes
+    pub to: usize,
+    pub kind: GraphKind,
+    /// Directory of the result files
+    pub output_dir: PathBuf,
+    pub quiet: bool,
+    /// Seed of the random DAGs, drawn at random if not given
+    pub seed: Option<u64>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            from: 5,
+            to: 150,
+            kind: GraphKind::Chain,
+            output_dir: PathBuf::from("."),
+            quiet: false,
+            seed: None,
+        }
+    }
+}
+
+/// Runs the benchmark, writing `resultsListes.txt` and `resultsHashmap.txt` (size and time of
+/// each resolution), `comparaisonResult.txt` (whether both gave the same integral) and
+/// `integrales.txt` (the integrals, which can be given to `rustegrator integrate --file`),
+/// each one starting with a `// seed` line.
+/// Returns the number of sizes for which the two resolutions differ. Fails before running
+/// anything if the DAGs of size `to` cannot be generated (see `GraphKind::max_size`).
+pub fn run_bench(options: &BenchOptions) -> Result<usize, String> {
+    options.kind.check_size(options.to)?;
+    fs::create_dir_all(&options.output_dir)
+        .map_err(|e| format!("Cannot create {}: {e}", options.output_dir.display()))?;
+    let create = |name: &str| {
+        let path = options.output_dir.join(name);
+        File::create(&path)
+            .map(BufWriter::new)
+            .map_err(|e| format!("Unable to create {}: {e}", path.display()))
+    };
+    let mut file = create("resultsListes.txt")?;
+    let mut file2 = create("resultsHashmap.txt")?;
+    let mut file3 = create("comparaisonResult.txt")?;
+    let mut integrales = create("integrales.txt")?;
+    let write_error = |e: std::io::Error| format!("Unable to write to file: {e}");
+    let seed = options.seed.unwrap_or_else(random_seed);
+    for f in [&mut file, &mut file2, &mut file3, &mut integrales] {
+        writeln!(f, "// seed {seed}").map_err(write_error)?;
+    }
+    if !options.quiet {
+        println!("seed {seed}");
+    }
+
+    let mut differences = 0;
+    for size in options.from..=options.to {
+        let matrix = options.kind.generate(size, &mut rng_from_seed(sub_seed(seed, size as u64)))?;
+        let mut g = Graph::from_matrice(matrix.clone());
+        let start_time = Instant::now();
+        let results = list_BIT_resolution(matrix);
+        let duration = start_time.elapsed();
+
+        let start_time2 = Instant::now();
+        let results2 = g.resolution_adjacence();
+        let duration2 = start_time2.elapsed();
+
+        // Comparaison des résultats
+        let compar = results == results2;
+        if !compar {
+            differences += 1;
+        }
+
+        writeln!(file, "{} {:?}", size, duration).map_err(write_error)?;
+        writeln!(file2, "{} {:?}", size, duration2).map_err(write_error)?;
+        writeln!(file3, "Comparaison: {}", compar).map_err(write_error)?;
+        match &results {
+            Ok(integral) => writeln!(integrales, "{}", integral),
+            Err(e) => writeln!(integrales, "// error {}", e),
+        }
+        .map_err(write_error)?;
+
+        if !options.quiet {
+            println!("{size:>4} nodes  lists {:>12?}  hashmap {:>12?}  same result: {compar}", duration, duration2);
+        }
+    }
+    for f in [&mut file, &mut file2, &mut file3, &mut integrales] {
+        f.flush().map_err(write_error)?;
+    }
+    Ok(differences)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::{run_bench, BenchOptions};
+    use crate::generator_matrix::GraphKind;
+
+    #[test]
+    fn test_bench_files() {
+        let dir = env::temp_dir().join(format!("rustegrator-test-bench-{}", std::process::id()));
+        let options = BenchOptions {
+            from: 3,
+            to: 6,
+            output_dir: dir.clone(),
+            quiet: true,
+            kind: GraphKind::Random,
+            seed: Some(7),
+        };
+        assert_eq!(run_bench(&options), Ok(0));
+        let boolean = BenchOptions { kind: GraphKind::Boolean, to: 13, ..options.clone() };
+        assert_eq!(run_bench(&boolean), Err("The size of a boolean DAG is at most 12 (2^12 nodes), found 13".to_string()));
+        let integrales = fs::read_to_string(dir.join("integrales.txt")).unwrap();
+        assert_eq!(integrales.lines().count(), 5);
+        assert_eq!(integrales.lines().next(), Some("// seed 7"));
+        let resultats = fs::read_to_string(dir.join("resultsListes.txt")).unwrap();
+        assert_eq!(resultats.lines().nth(1).unwrap().split(' ').next(), Some("3"));
+
+        // the DAG of size 5 is the same when run alone
+        run_bench(&BenchOptions { from: 5, to: 5, ..options.clone() }).unwrap();
+        let seule = fs::read_to_string(dir.join("integrales.txt")).unwrap();
+        assert_eq!(seule.lines().nth(1), integrales.lines().nth(3));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,327 @@
+// This module provides a bit-matrix representation of the directed graphs: the row of a node
+// holds its successors and its column its predecessors, 64 nodes per word, so that the unions
+// of rows, the removal of a node and the reachability searches work word by word, and the
+// degrees are kept up to date. The transitive closure and reduction and the BIT resolution
+// use it, which makes graphs of thousands of nodes practical.
+
+const BITS: usize = u64::BITS as usize;
+
+/// Adjacency matrix of a directed graph on the nodes `0..n`, stored as bits by rows and by
+/// columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitMatrix {
+    n: usize,
+    /// Number of words of a row or a column
+    mots: usize,
+    /// The successors of each node, `mots` words per node
+    lignes: Vec<u64>,
+    /// The predecessors of each node, `mots` words per node
+    colonnes: Vec<u64>,
+    entrants: Vec<usize>,
+    sortants: Vec<usize>,
+}
+
+impl BitMatrix {
+    /// The graph of `n` nodes without edges.
+    pub fn new(n: usize) -> BitMatrix {
+        let mots = n.div_ceil(BITS);
+        BitMatrix {
+            n,
+            mots,
+            lignes: vec![0; n * mots],
+            colonnes: vec![0; n * mots],
+            entrants: vec![0; n],
+            sortants: vec![0; n],
+        }
+    }
+
+    /// Converts an adjacency matrix of 0/1 entries.
+    pub fn from_matrice(matrice: &[Vec<i32>]) -> BitMatrix {
+        let mut res = BitMatrix::new(matrice.len());
+        for (i, ligne) in matrice.iter().enumerate() {
+            for (j, _) in ligne.iter().enumerate().filter(|(_, &v)| v == 1) {
+                res.set(i, j);
+            }
+        }
+        res
+    }
+
+    /// Converts a relation matrix, `relation[i][j]` being true for an edge `i -> j`.
+    pub fn from_relation(relation: &[Vec<bool>]) -> BitMatrix {
+        let mut res = BitMatrix::new(relation.len());
+        for (i, ligne) in relation.iter().enumerate() {
+            for (j, _) in ligne.iter().enumerate().filter(|(_, &v)| v) {
+                res.set(i, j);
+            }
+        }
+        res
+    }
+
+    /// Builds the graph from its rows of successors, `mots` words per node.
+    fn from_rows(n: usize, lignes: Vec<u64>) -> BitMatrix {
+        let mut res = BitMatrix::new(n);
+        for i in 0..n {
+            for j in bits(&lignes[i * res.mots..(i + 1) * res.mots]) {
+                res.set(i, j);
+            }
+        }
+        res
+    }
+
+    /// The adjacency matrix of 0/1 entries.
+    pub fn to_matrice(&self) -> Vec<Vec<i32>> {
+        (0..self.n).map(|i| (0..self.n).map(|j| self.get(i, j) as i32).collect()).collect()
+    }
+
+    /// The relation matrix, `relation[i][j]` being true for an edge `i -> j`.
+    pub fn to_relation(&self) -> Vec<Vec<bool>> {
+        (0..self.n).map(|i| (0..self.n).map(|j| self.get(i, j)).collect()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Whether there is an edge `i -> j`.
+    pub fn get(&self, i: usize, j: usize) -> bool {
+        self.lignes[i * self.mots + j / BITS] >> (j % BITS) & 1 == 1
+    }
+
+    /// Adds the edge `i -> j`.
+    pub fn set(&mut self, i: usize, j: usize) {
+        if !self.get(i, j) {
+            self.lignes[i * self.mots + j / BITS] |= 1 << (j % BITS);
+            self.colonnes[j * self.mots + i / BITS] |= 1 << (i % BITS);
+            self.sortants[i] += 1;
+            self.entrants[j] += 1;
+        }
+    }
+
+    /// Removes the edge `i -> j`.
+    pub fn unset(&mut self, i: usize, j: usize) {
+        if self.get(i, j) {
+            self.lignes[i * self.mots + j / BITS] &= !(1 << (j % BITS));
+            self.colonnes[j * self.mots + i / BITS] &= !(1 << (i % BITS));
+            self.sortants[i] -= 1;
+            self.entrants[j] -= 1;
+        }
+    }
+
+    /// Removes all the edges into and out of `i`.
+    pub fn isolate(&mut self, i: usize) {
+        let successeurs: Vec<usize> = self.successors(i).collect();
+        let predecesseurs: Vec<usize> = self.predecessors(i).collect();
+        for j in successeurs {
+            self.unset(i, j);
+        }
+        for j in predecesseurs {
+            self.unset(j, i);
+        }
+    }
+
+    /// The successors of `i` as words of bits.
+    pub fn row(&self, i: usize) -> &[u64] {
+        &self.lignes[i * self.mots..(i + 1) * self.mots]
+    }
+
+    /// The predecessors of `j` as words of bits.
+    pub fn column(&self, j: usize) -> &[u64] {
+        &self.colonnes[j * self.mots..(j + 1) * self.mots]
+    }
+
+    pub fn out_degree(&self, i: usize) -> usize {
+        self.sortants[i]
+    }
+
+    pub fn in_degree(&self, j: usize) -> usize {
+        self.entrants[j]
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.sortants.iter().sum()
+    }
+
+    /// The successors of `i`, in increasing order.
+    pub fn successors(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        bits(self.row(i))
+    }
+
+    /// The predecessors of `j`, in increasing order.
+    pub fn predecessors(&self, j: usize) -> impl Iterator<Item = usize> + '_ {
+        bits(self.column(j))
+    }
+
+    /// Whether there is a path (of at least one edge) from `i` to `j`.
+    pub fn reaches(&self, i: usize, j: usize) -> bool {
+        if self.sortants[i] == 0 || self.entrants[j] == 0 {
+            return false;
+        }
+        let mut vus = vec![0u64; self.mots];
+        let mut pile = vec![i];
+        while let Some(noeud) = pile.pop() {
+            if self.get(noeud, j) {
+                return true;
+            }
+            // the successors not seen yet, a word at a time
+            for (k, (vu, &mot)) in vus.iter_mut().zip(self.row(noeud)).enumerate() {
+                let nouveaux = mot & !*vu;
+                *vu |= nouveaux;
+                pile.extend(bits(&[nouveaux]).map(|b| k * BITS + b));
+            }
+        }
+        false
+    }
+
+    /// Sorts the nodes topologically (Kahn's algorithm).
+    ///
+    /// # Returns
+    ///
+    /// The nodes, each one before its successors, or a node on a cycle if the graph has one.
+    pub fn topological_order(&self) -> Result<Vec<usize>, usize> {
+        let mut degres = self.entrants.clone();
+        let mut ordre: Vec<usize> = (0..self.n).filter(|&j| degres[j] == 0).collect();
+        let mut k = 0;
+        while k < ordre.len() {
+            for j in self.successors(ordre[k]) {
+                degres[j] -= 1;
+                if degres[j] == 0 {
+                    ordre.push(j);
+                }
+            }
+            k += 1;
+        }
+        if ordre.len() == self.n {
+            return Ok(ordre);
+        }
+        // each node left has a predecessor left: going up from one of them ends on a cycle
+        let mut vus = vec![false; self.n];
+        let mut noeud = (0..self.n).find(|&j| degres[j] > 0).unwrap();
+        while !vus[noeud] {
+            vus[noeud] = true;
+            noeud = self.predecessors(noeud).find(|&i| degres[i] > 0).unwrap();
+        }
+        Err(noeud)
+    }
+
+    /// Computes the transitive closure, in reverse topological order: the row of a node is the
+    /// union of its successors and of their rows.
+    ///
+    /// # Returns
+    ///
+    /// The graph of the strict order, with an edge `i -> j` for each path from `i` to `j`, or
+    /// a node on a cycle if the graph has one.
+    pub fn transitive_closure(&self) -> Result<BitMatrix, usize> {
+        let mut lignes = self.lignes.clone();
+        for i in self.topological_order()?.into_iter().rev() {
+            for j in self.successors(i) {
+                for k in 0..self.mots {
+                    lignes[i * self.mots + k] |= lignes[j * self.mots + k];
+                }
+            }
+        }
+        Ok(BitMatrix::from_rows(self.n, lignes))
+    }
+
+    /// Computes the transitive reduction: an edge `i -> j` is kept when no other successor of
+    /// `i` reaches `j`.
+    ///
+    /// # Returns
+    ///
+    /// The graph of the edges which are the only path between their ends, or a node on a
+    /// cycle if the graph has one.
+    pub fn transitive_reduction(&self) -> Result<BitMatrix, usize> {
+        Ok(self.transitive_closure()?.reduce_order(self))
+    }
+
+    /// The edges of `graphe` which are the only path between their ends, `self` being the
+    /// transitive closure of `graphe`.
+    fn reduce_order(&self, graphe: &BitMatrix) -> BitMatrix {
+        let mut lignes = graphe.lignes.clone();
+        let mut atteints = vec![0u64; self.mots];
+        for i in 0..self.n {
+            atteints.fill(0);
+            for k in graphe.successors(i) {
+                for (atteint, &mot) in atteints.iter_mut().zip(self.row(k)) {
+                    *atteint |= mot;
+                }
+            }
+            for (mot, &atteint) in lignes[i * self.mots..(i + 1) * self.mots].iter_mut().zip(&atteints) {
+                *mot &= !atteint;
+            }
+        }
+        BitMatrix::from_rows(self.n, lignes)
+    }
+
+    /// The cover graph of a strict order, `self` being its (transitive) relation: `i` is
+    /// covered by `j` when `i < j` with no element in between.
+    pub fn covers(&self) -> BitMatrix {
+        self.reduce_order(self)
+    }
+}
+
+/// The indices of the bits set in a sequence of words, in increasing order.
+pub fn bits(mots: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    mots.iter().enumerate().flat_map(|(k, &mot)| {
+        let mut reste = mot;
+        std::iter::from_fn(move || {
+            if reste == 0 {
+                return None;
+            }
+            let b = reste.trailing_zeros() as usize;
+            reste &= reste - 1;
+            Some(k * BITS + b)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bits, BitMatrix};
+    use crate::generator_matrix::{erdos_renyi_generator, rng_from_seed};
+
+    #[test]
+    fn test_bit_matrix() {
+        // across the words: 130 nodes, 0 -> 64 -> 129 and 0 -> 1
+        let mut graphe = BitMatrix::new(130);
+        graphe.set(0, 64);
+        graphe.set(64, 129);
+        graphe.set(0, 1);
+        graphe.set(0, 1);
+        assert_eq!(graphe.edge_count(), 3);
+        assert_eq!((graphe.out_degree(0), graphe.in_degree(64), graphe.in_degree(1)), (2, 1, 1));
+        assert_eq!(graphe.successors(0).collect::<Vec<usize>>(), vec![1, 64]);
+        assert_eq!(graphe.predecessors(129).collect::<Vec<usize>>(), vec![64]);
+        assert!(graphe.reaches(0, 129) && !graphe.reaches(1, 129) && !graphe.reaches(129, 0));
+        let closure = graphe.transitive_closure().unwrap();
+        assert!(closure.get(0, 129) && closure.edge_count() == 4);
+        assert_eq!(closure.covers(), graphe);
+        graphe.isolate(64);
+        assert_eq!(graphe.edge_count(), 1);
+        assert!(!graphe.reaches(0, 129));
+        graphe.unset(0, 1);
+        assert_eq!(graphe, BitMatrix::new(130));
+        assert_eq!(bits(&[0b101, 1 << 63]).collect::<Vec<usize>>(), vec![0, 2, 127]);
+
+        graphe.set(5, 70);
+        graphe.set(70, 5);
+        assert!([5, 70].contains(&graphe.topological_order().unwrap_err()));
+    }
+
+    #[test]
+    fn test_conversions() {
+        for seed in 0..10 {
+            let matrice = erdos_renyi_generator(70, 0.1, &mut rng_from_seed(seed));
+            let graphe = BitMatrix::from_matrice(&matrice);
+            assert_eq!(graphe.to_matrice(), matrice);
+            assert_eq!(BitMatrix::from_relation(&graphe.to_relation()), graphe);
+            for i in 0..70 {
+                assert_eq!(graphe.out_degree(i), matrice[i].iter().sum::<i32>() as usize);
+                assert_eq!(graphe.in_degree(i), matrice.iter().map(|ligne| ligne[i]).sum::<i32>() as usize);
+            }
+        }
+    }
+}
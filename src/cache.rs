@@ -0,0 +1,261 @@
+// This module implements the on-disk cache of integration results. Entries are keyed by
+// the canonical form of the integral (variables renamed x0, x1, ... in integration order),
+// so that the same integral written with other variable names is found in the cache.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use num::BigRational;
+
+use crate::integral::{Bound, IntegralSpec};
+use crate::version::{VERSION_MAJOR, VERSION_MINOR};
+
+/// A cached result with its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEntry {
+    pub canonical: String,
+    pub result: BigRational,
+    pub nbvars: usize,
+    /// Time the integration took
+    pub time: Duration,
+    /// Creation date, in seconds since the Unix epoch
+    pub created: u64,
+}
+
+/// Returns the default cache directory: `$XDG_CACHE_HOME/rustegrator`, or
+/// `$HOME/.cache/rustegrator`, or `.rustegrator-cache` in the current directory.
+pub fn default_cache_dir() -> PathBuf {
+    match (env::var_os("XDG_CACHE_HOME"), env::var_os("HOME")) {
+        (Some(dir), _) if !dir.is_empty() => Path::new(&dir).join("rustegrator"),
+        (_, Some(home)) if !home.is_empty() => Path::new(&home).join(".cache").join("rustegrator"),
+        _ => PathBuf::from(".rustegrator-cache"),
+    }
+}
+
+/// Canonical form of an integral: the variables are renamed `x0`, `x1`, ... in the
+/// order in which they are integrated.
+pub fn canonical_form(spec: &IntegralSpec) -> String {
+    let renaming: HashMap<usize, String> = spec
+        .elements
+        .iter()
+        .enumerate()
+        .map(|(i, (var, _, _))| (*var, format!("x{i}")))
+        .collect();
+    let rename = |bound: &Bound| match bound {
+        Bound::Var(name) => match spec.var_map.get(name).and_then(|var| renaming.get(var)) {
+            Some(canonical) => canonical.clone(),
+            None => name.clone(),
+        },
+        bound => bound.to_string(),
+    };
+    let mut res = String::new();
+    for (_, b1, b2) in spec.elements.iter().rev() {
+        res.push_str(&format!("Int_{}^{} ", rename(b1), rename(b2)));
+    }
+    res.push('1');
+    for i in 0..spec.elements.len() {
+        res.push_str(&format!(" dx{i}"));
+    }
+    res
+}
+
+/// Stable 64-bit FNV-1a hash, used to name the cache files.
+fn fnv1a(input: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in input.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Hash of the canonical form of an integral, as a hexadecimal string.
+pub fn canonical_hash(spec: &IntegralSpec) -> String {
+    format!("{:016x}", fnv1a(&canonical_form(spec)))
+}
+
+/// Number of the temporary files written by this process, to name them.
+static TEMPORAIRES: AtomicUsize = AtomicUsize::new(0);
+
+/// A cache file with its content (`Err` if it cannot be read).
+pub type EntryFile = (PathBuf, Result<CacheEntry, String>);
+
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Cache {
+        Cache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn entry_path(&self, canonical: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.entry", fnv1a(canonical)))
+    }
+
+    /// Looks up the result of an integral. Unreadable entries are treated as missing.
+    pub fn get(&self, spec: &IntegralSpec) -> Option<CacheEntry> {
+        let canonical = canonical_form(spec);
+        let content = fs::read_to_string(self.entry_path(&canonical)).ok()?;
+        // the canonical form is checked to rule out hash collisions
+        parse_entry(&content).ok().filter(|entry| entry.canonical == canonical)
+    }
+
+    pub fn put(&self, spec: &IntegralSpec, result: &BigRational, time: Duration) -> Result<(), String> {
+        let entry = CacheEntry {
+            canonical: canonical_form(spec),
+            result: result.clone(),
+            nbvars: spec.elements.len(),
+            time,
+            created: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        };
+        fs::create_dir_all(&self.dir).map_err(|e| format!("Cannot create cache {}: {e}", self.dir.display()))?;
+        let path = self.entry_path(&entry.canonical);
+        // each writer has its own temporary file, so that the renamed one is always complete
+        let numero = TEMPORAIRES.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = path.with_extension(format!("{}.{numero}.tmp", std::process::id()));
+        fs::write(&tmp_path, format_entry(&entry))
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|e| format!("Cannot write cache entry {}: {e}", path.display()))
+    }
+
+    /// Lists the entries of the cache, with the path of their file (`Err` for unreadable ones).
+    pub fn entries(&self) -> Result<Vec<EntryFile>, String> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("Cannot read cache {}: {e}", self.dir.display())),
+        };
+        let mut entries = Vec::new();
+        for file in dir.flatten() {
+            let path = file.path();
+            if path.extension().is_some_and(|ext| ext == "entry") {
+                let entry = fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|c| parse_entry(&c));
+                entries.push((path, entry));
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// Removes the entries older than `older_than` (all of them if `None`) and the unreadable
+    /// ones. Returns the number of entries removed.
+    pub fn prune(&self, older_than: Option<Duration>) -> Result<usize, String> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let mut removed = 0;
+        for (path, entry) in self.entries()? {
+            let remove = match (&entry, older_than) {
+                (Err(_), _) | (Ok(_), None) => true,
+                (Ok(entry), Some(age)) => now.saturating_sub(entry.created) > age.as_secs(),
+            };
+            if remove {
+                fs::remove_file(&path).map_err(|e| format!("Cannot remove {}: {e}", path.display()))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+fn format_entry(entry: &CacheEntry) -> String {
+    format!(
+        "rustegrator-cache {VERSION_MAJOR}.{VERSION_MINOR}\ncanonical {}\nresult {}\nvars {}\ntime_us {}\ncreated {}\n",
+        entry.canonical,
+        entry.result,
+        entry.nbvars,
+        entry.time.as_micros(),
+        entry.created
+    )
+}
+
+fn parse_entry(content: &str) -> Result<CacheEntry, String> {
+    let mut fields = HashMap::new();
+    for line in content.lines() {
+        if let Some((key, value)) = line.split_once(' ') {
+            fields.insert(key, value);
+        }
+    }
+    let field = |key: &str| fields.get(key).copied().ok_or_else(|| format!("Missing {key} in cache entry"));
+    let number = |key: &str| field(key).and_then(|v| v.parse::<u64>().map_err(|e| format!("Bad {key}: {e}")));
+    field("rustegrator-cache")?;
+    Ok(CacheEntry {
+        canonical: field("canonical")?.to_string(),
+        result: BigRational::from_str(field("result")?).map_err(|e| format!("Bad result: {e}"))?,
+        nbvars: number("vars")? as usize,
+        time: Duration::from_micros(number("time_us")?),
+        created: number("created")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+
+    use num::{BigInt, BigRational};
+
+    use super::{canonical_form, canonical_hash, Cache};
+    use crate::parser::parse;
+
+    #[test]
+    fn test_canonical_form() {
+        let spec1 = parse("Int_0^1 Int_0^x3 Int_x3^1 1 dx1 dx5 dx3").unwrap();
+        let spec2 = parse("Int_0^1 Int_0^x_7 Int_x_7^1 1 dx_2 dx_9 dx_7").unwrap();
+        assert_eq!(canonical_form(&spec1), "Int_0^1 Int_0^x2 Int_x2^1 1 dx0 dx1 dx2");
+        assert_eq!(canonical_form(&spec1), canonical_form(&spec2));
+        assert_eq!(canonical_hash(&spec1), canonical_hash(&spec2));
+        let spec3 = parse("Int_0^1 Int_x3^1 Int_0^x3 1 dx1 dx5 dx3").unwrap();
+        assert_ne!(canonical_hash(&spec1), canonical_hash(&spec3));
+    }
+
+    #[test]
+    fn test_put_get_prune() {
+        let dir = env::temp_dir().join(format!("rustegrator-test-cache-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let spec = parse("Int_0^1 Int_0^x2 1 dx1 dx2").unwrap();
+        let renamed = parse("Int_0^1 Int_0^x_4 1 dx_3 dx_4").unwrap();
+        assert!(cache.get(&spec).is_none());
+
+        let half = BigRational::new(BigInt::from(1), BigInt::from(2));
+        cache.put(&spec, &half, Duration::from_micros(42)).unwrap();
+        let entry = cache.get(&renamed).unwrap();
+        assert_eq!(entry.result, half);
+        assert_eq!(entry.nbvars, 2);
+        assert_eq!(entry.time, Duration::from_micros(42));
+
+        fs::write(dir.join("bogus.entry"), "garbage").unwrap();
+        assert_eq!(cache.entries().unwrap().len(), 2);
+        assert_eq!(cache.prune(Some(Duration::from_secs(3600))).unwrap(), 1);
+        assert_eq!(cache.prune(None).unwrap(), 1);
+        assert!(cache.get(&spec).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_put() {
+        let dir = env::temp_dir().join(format!("rustegrator-test-cache-concurrent-{}", std::process::id()));
+        let spec = parse("Int_0^1 Int_0^x2 1 dx1 dx2").unwrap();
+        let half = BigRational::new(BigInt::from(1), BigInt::from(2));
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..20 {
+                        Cache::new(&dir).put(&spec, &half, Duration::from_micros(42)).unwrap();
+                    }
+                });
+            }
+        });
+        let cache = Cache::new(&dir);
+        assert_eq!(cache.get(&spec).unwrap().result, half);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1, "temporary files left");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
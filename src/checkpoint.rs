@@ -0,0 +1,118 @@
+// This module saves and restores the state of a running integration (the current
+// polynomial, the number of steps already done and the integral being computed),
+// so that long integrations can be resumed after a crash or a Ctrl-C.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+use crate::integral::IntegralSpec;
+use crate::integrator::Poly;
+use crate::parser;
+
+const CHECKPOINT_HEADER: &str = "rustegrator-checkpoint 1";
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static INSTALL_HANDLER: Once = Once::new();
+
+/// State of an integration after `step` integration steps.
+#[derive(Debug)]
+pub struct Checkpoint {
+    pub spec: IntegralSpec,
+    pub step: usize,
+    pub poly: Poly,
+}
+
+impl Checkpoint {
+    /// Writes the checkpoint to `path`. The file is written next to its destination
+    /// then renamed, so that an existing checkpoint is never left half-written.
+    pub fn save(path: &str, spec: &IntegralSpec, step: usize, poly: &Poly) -> Result<(), String> {
+        let tmp_path = format!("{path}.tmp");
+        let write = || -> std::io::Result<()> {
+            let mut file = File::create(&tmp_path)?;
+            writeln!(file, "{CHECKPOINT_HEADER}")?;
+            writeln!(file, "spec {spec}")?;
+            writeln!(file, "step {step}")?;
+            write!(file, "{}", poly.serialize())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, path)
+        };
+        write().map_err(|e| format!("Cannot write checkpoint {path}: {e}"))
+    }
+
+    pub fn load(path: &str) -> Result<Checkpoint, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Cannot read checkpoint {path}: {e}"))?;
+        let mut parts = content.splitn(4, '\n');
+
+        if parts.next() != Some(CHECKPOINT_HEADER) {
+            return Err(format!("{path} is not a checkpoint file"));
+        }
+        let spec = match parts.next().and_then(|l| l.strip_prefix("spec ")) {
+            Some(source) => parser::parse(source)?,
+            None => return Err("Missing integral in checkpoint".to_string()),
+        };
+        let step = match parts.next().and_then(|l| l.strip_prefix("step ")).map(|s| s.parse::<usize>()) {
+            Some(Ok(step)) if step <= spec.elements.len() => step,
+            _ => return Err("Bad step in checkpoint".to_string()),
+        };
+        let poly = Poly::deserialize(parts.next().unwrap_or(""))?;
+        if poly.nbvars() != spec.elements.len() {
+            return Err("Checkpoint polynomial does not match its integral".to_string());
+        }
+        Ok(Checkpoint { spec, step, poly })
+    }
+}
+
+/// Catches Ctrl-C: instead of killing the process, it sets a flag that the
+/// integration checks (see `interrupted`) to write a last checkpoint and stop.
+pub fn install_interrupt_handler() {
+    INSTALL_HANDLER.call_once(|| {
+        if let Err(e) = ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::SeqCst)) {
+            eprintln!("Cannot install the Ctrl-C handler: {e}");
+        }
+    });
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::Checkpoint;
+    use crate::integrator::{poly_pp, Poly};
+    use crate::parser::parse;
+
+    #[test]
+    fn test_save_load() {
+        let spec = parse("Int_0^1 Int_0^x3 Int_x3^1 Int_x3^1 Int_0^x3 1 dx1 dx5 dx4 dx2 dx3").unwrap();
+        let mut poly = Poly::new(spec.elements.len());
+        for (var, from, to) in spec.elements.iter().take(3) {
+            poly = poly.integrate(&spec, *var, from, to);
+        }
+        let path = env::temp_dir().join(format!("rustegrator-test-{}.ckpt", std::process::id()));
+        let path = path.to_str().unwrap();
+        Checkpoint::save(path, &spec, 3, &poly).unwrap();
+
+        let ckpt = Checkpoint::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(ckpt.step, 3);
+        assert_eq!(ckpt.spec.to_string(), spec.to_string());
+        assert_eq!(poly_pp(&ckpt.spec, &ckpt.poly), poly_pp(&spec, &poly));
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let path = env::temp_dir().join(format!("rustegrator-test-bad-{}.ckpt", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "rustegrator-checkpoint 1\nspec Int_0^1 1 dx1\nstep 4\npoly 1 1\n1 0\n").unwrap();
+        let res = Checkpoint::load(path);
+        fs::remove_file(path).unwrap();
+        assert!(res.is_err());
+        assert!(Checkpoint::load("/nonexistent/checkpoint").is_err());
+    }
+}
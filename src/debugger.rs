@@ -0,0 +1,231 @@
+// This module implements the interactive step debugger (`--debug`): the integration
+// is run one variable at a time, and between the steps the current polynomial can be
+// printed, measured, evaluated or saved to a checkpoint file.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use std::time::Instant;
+
+use num::{BigInt, BigRational};
+
+use crate::checkpoint::Checkpoint;
+use crate::integral::IntegralSpec;
+use crate::integrator::{poly_pp, Poly};
+
+const HELP: &str = "\
+Commands:
+  s, step [n]          integrate the next n variables (default 1)
+  c, continue          integrate until the end
+  j, jump <k>          go to the state after k steps (0 is the initial state)
+  p, print             print the current polynomial
+  stats                show statistics about the current polynomial
+  e, eval x1=1/2 ...   evaluate the current polynomial at a point
+  save <file>          save the current state to a checkpoint file (see --resume)
+  h, help              show this help
+  q, quit              stop the integration";
+
+/// Result of a debugging session.
+#[derive(Debug, PartialEq)]
+pub enum DebugOutcome {
+    /// The integration went to the end, with this polynomial
+    Finished(Poly),
+    Quit,
+}
+
+pub struct Debugger<'a> {
+    spec: &'a IntegralSpec,
+    poly: Poly,
+    /// Number of integration steps already done
+    step: usize,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(spec: &'a IntegralSpec, poly: Poly, step: usize) -> Debugger<'a> {
+        Debugger { spec, poly, step }
+    }
+
+    /// Reads commands from `input` until the integration is finished or the user quits.
+    pub fn run<R: BufRead, W: Write>(mut self, input: &mut R, out: &mut W) -> Result<DebugOutcome, String> {
+        let io_error = |e: std::io::Error| format!("Debugger I/O error: {e}");
+        writeln!(out, "{HELP}").map_err(io_error)?;
+        loop {
+            if self.finished() {
+                writeln!(out, "Integration finished: {}", poly_pp(self.spec, &self.poly)).map_err(io_error)?;
+                return Ok(DebugOutcome::Finished(self.poly));
+            }
+            write!(out, "{}> ", self.describe_next()).map_err(io_error)?;
+            out.flush().map_err(io_error)?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line).map_err(io_error)? == 0 {
+                return Ok(DebugOutcome::Quit);
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let msg = match words.as_slice() {
+                [] => continue,
+                ["s"] | ["step"] => self.step_n(1),
+                ["s", n] | ["step", n] => match n.parse::<usize>() {
+                    Ok(n) => self.step_n(n),
+                    Err(_) => format!("Bad number of steps: {n}"),
+                },
+                ["c"] | ["continue"] => self.step_n(self.spec.elements.len()),
+                ["j", k] | ["jump", k] => match k.parse::<usize>() {
+                    Ok(k) => self.jump(k),
+                    Err(_) => format!("Bad step: {k}"),
+                },
+                ["p"] | ["print"] => poly_pp(self.spec, &self.poly),
+                ["stats"] => {
+                    let stats = self.poly.stats();
+                    format!(
+                        "#monomials={} #coefficients={} max_degree={} numer_bits={} denom_bits={} memory={}B",
+                        stats.nb_monos,
+                        stats.nb_coefs,
+                        stats.max_degree,
+                        stats.max_numer_bits,
+                        stats.max_denom_bits,
+                        stats.memory_bytes
+                    )
+                }
+                ["e", point @ ..] | ["eval", point @ ..] => match self.eval(point) {
+                    Ok(value) => value.to_string(),
+                    Err(e) => e,
+                },
+                ["save", path] => match Checkpoint::save(path, self.spec, self.step, &self.poly) {
+                    Ok(()) => format!("State after step {} saved to {path}", self.step),
+                    Err(e) => e,
+                },
+                ["h"] | ["help"] => HELP.to_string(),
+                ["q"] | ["quit"] => return Ok(DebugOutcome::Quit),
+                _ => format!("Unknown command: {} (type help)", line.trim()),
+            };
+            writeln!(out, "{msg}").map_err(io_error)?;
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.step >= self.spec.elements.len()
+    }
+
+    fn describe_next(&self) -> String {
+        let (var, from, to) = &self.spec.elements[self.step];
+        format!(
+            "[step {}/{}: Int_{}^{} d{}]",
+            self.step + 1,
+            self.spec.elements.len(),
+            from,
+            to,
+            self.spec.var_name(*var)
+        )
+    }
+
+    /// Integrates the next `n` variables (or less if the end is reached).
+    fn step_n(&mut self, n: usize) -> String {
+        let now = Instant::now();
+        let first = self.step;
+        while self.step < first + n && !self.finished() {
+            let (var, from, to) = &self.spec.elements[self.step];
+            self.poly = self.poly.integrate(self.spec, *var, from, to);
+            self.step += 1;
+        }
+        format!(
+            "{} step(s) in {:?}, {} monomials",
+            self.step - first,
+            now.elapsed(),
+            self.poly.number_of_monos()
+        )
+    }
+
+    /// Goes to the state after `k` steps, integrating from the start when going backward.
+    fn jump(&mut self, k: usize) -> String {
+        if k > self.spec.elements.len() {
+            return format!("No step {k}, the integral has {} steps", self.spec.elements.len());
+        }
+        if k < self.step {
+            self.poly = Poly::new(self.spec.elements.len());
+            self.step = 0;
+        }
+        self.step_n(k - self.step)
+    }
+
+    fn eval(&self, point: &[&str]) -> Result<BigRational, String> {
+        let mut values = HashMap::new();
+        for assignment in point {
+            let (name, value) = assignment
+                .split_once('=')
+                .ok_or_else(|| format!("Expected var=value, found {assignment}"))?;
+            let var_ref = match self.spec.var_map.get(name) {
+                Some(var_ref) => *var_ref,
+                None => return Err(format!("No such variable: {name}")),
+            };
+            values.insert(var_ref, parse_rational(value)?);
+        }
+        self.poly.eval(self.spec, &values)
+    }
+}
+
+/// Parses a rational number written as an integer, a fraction (`1/3`) or a decimal (`0.25`).
+fn parse_rational(input: &str) -> Result<BigRational, String> {
+    let bad = || format!("Bad number: {input}");
+    match input.split_once('.') {
+        Some((int_part, frac_part)) => {
+            let digits = BigInt::from_str(&format!("{int_part}{frac_part}")).map_err(|_| bad())?;
+            let denom = BigInt::from(10).pow(frac_part.len() as u32);
+            Ok(BigRational::new(digits, denom))
+        }
+        None => BigRational::from_str(input).map_err(|_| bad()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{parse_rational, DebugOutcome, Debugger};
+    use crate::integrator::{poly_pp, Poly};
+    use crate::parser::parse;
+
+    fn run(commands: &str) -> (Result<DebugOutcome, String>, String) {
+        let spec = parse("Int_0^1 Int_0^x3 Int_x3^1 Int_x3^1 Int_0^x3 1 dx1 dx5 dx4 dx2 dx3").unwrap();
+        let debugger = Debugger::new(&spec, Poly::new(spec.elements.len()), 0);
+        let mut out = Vec::new();
+        let res = debugger.run(&mut Cursor::new(commands), &mut out);
+        (res, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_step_print_eval() {
+        let (res, out) = run("step 2\nprint\neval x3=1/2\neval x1=1\nquit\n");
+        assert_eq!(res, Ok(DebugOutcome::Quit));
+        assert!(out.contains("-x3^2 + x3\n"));
+        assert!(out.contains("1/4\n"));
+        assert!(out.contains("No value for x3"));
+    }
+
+    #[test]
+    fn test_jump_and_continue() {
+        let (res, out) = run("jump 4\njump 2\np\nc\n");
+        assert!(out.contains("[step 5/5: Int_0^1 dx3]"));
+        assert!(out.contains("-x3^2 + x3\n"));
+        let spec = parse("Int_0^1 1 dx1").unwrap();
+        match res {
+            Ok(DebugOutcome::Finished(poly)) => assert_eq!(poly_pp(&spec, &poly), "1/30"),
+            other => panic!("Unexpected outcome {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_end_of_input() {
+        let (res, out) = run("bogus\n");
+        assert_eq!(res, Ok(DebugOutcome::Quit));
+        assert!(out.contains("Unknown command: bogus"));
+    }
+
+    #[test]
+    fn test_parse_rational() {
+        assert_eq!(parse_rational("0.25").unwrap().to_string(), "1/4");
+        assert_eq!(parse_rational("2/6").unwrap().to_string(), "1/3");
+        assert_eq!(parse_rational("3").unwrap().to_string(), "3");
+        assert!(parse_rational("x").is_err());
+    }
+}
@@ -0,0 +1,521 @@
+// This module reads directed graphs in the Graphviz DOT language
+// (`digraph { a -> b; b -> c }`) as posets whose elements are named after the nodes.
+// Attributes (labels, shapes, graph settings) and ports are read but ignored.
+// It also writes adjacency matrices, and the steps of the BIT resolution, as DOT graphs.
+
+use std::collections::BTreeSet;
+
+use crate::poset::Poset;
+use crate::resolution_using_lists::{BitCase, BitError, BitStep};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier, number or quoted string
+    Id(String),
+    Punct(&'static str),
+}
+
+/// Returns true if `source` looks like a DOT graph (it starts with `digraph`, `graph` or `strict`).
+pub fn is_dot(source: &str) -> bool {
+    let first_word = strip_comments(source)
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .find(|w| !w.is_empty())
+        .map(|w| w.to_ascii_lowercase());
+    matches!(first_word.as_deref(), Some("digraph") | Some("graph") | Some("strict"))
+}
+
+/// Parses a DOT directed graph into a poset: each node is an element named after its id,
+/// each edge `a -> b` the relation `a < b`. Subgraphs are allowed on both sides of an edge
+/// (`{a b} -> c`).
+///
+/// # Arguments
+///
+/// * `source` - The DOT text.
+///
+/// # Returns
+///
+/// The poset, or an error with the line of the faulty statement.
+pub fn parse_dot(source: &str) -> Result<Poset, String> {
+    let mut parser = DotParser {
+        tokens: tokenize(source)?,
+        pos: 0,
+        poset: Poset::new(),
+    };
+    parser.graph()?;
+    Ok(parser.poset)
+}
+
+/// Formats an adjacency matrix as a DOT digraph whose node `i` is named `x<i>`, as in
+/// the integrals built by the BIT resolution.
+///
+/// # Arguments
+///
+/// * `matrice` - The adjacency matrix.
+/// * `name` - The name of the graph.
+///
+/// # Returns
+///
+/// The DOT text, ended by a newline.
+pub fn matrice_to_dot(matrice: &[Vec<i32>], name: &str) -> String {
+    let mut edges = Vec::new();
+    for (i, ligne) in matrice.iter().enumerate() {
+        edges.extend(ligne.iter().enumerate().filter(|(_, &v)| v == 1).map(|(j, _)| (i, j)));
+    }
+    edges_to_dot(matrice.len(), &edges, name)
+}
+
+/// Formats a graph given by its edges as a DOT digraph, see `matrice_to_dot`.
+pub fn edges_to_dot(size: usize, edges: &[(usize, usize)], name: &str) -> String {
+    let mut res = format!("digraph {name} {{\n");
+    for i in 0..size {
+        res += &format!("    x{i};\n");
+    }
+    for (i, j) in edges {
+        res += &format!("    x{i} -> x{j};\n");
+    }
+    res + "}\n"
+}
+
+/// Formats the steps of a BIT resolution as a sequence of DOT digraphs: the initial
+/// graph, then the graph after each step. The node integrated out at a step is dashed,
+/// and hidden in the next ones; the edges added by an intermediate case are in bold.
+/// `dot -Tsvg -O` renders each graph of the file in its own picture.
+///
+/// # Arguments
+///
+/// * `matrice` - The adjacency matrix of the DAG before the resolution.
+/// * `steps` - The steps returned by `resolution_steps`.
+/// * `integral` - The resulting integral, or the error of the resolution, shown on the last graph.
+///
+/// # Returns
+///
+/// The DOT text.
+pub fn steps_to_dot(matrice: &[Vec<i32>], steps: &[BitStep], integral: &Result<String, BitError>) -> String {
+    let integral = match integral {
+        Ok(integral) => integral.clone(),
+        Err(e) => e.to_string(),
+    };
+    let mut removed = vec![false; matrice.len()];
+    let mut res = String::new();
+    let mut previous = matrice;
+    let label = match steps.is_empty() {
+        true => format!("initial graph\\n{integral}"),
+        false => "initial graph".to_string(),
+    };
+    res += &step_to_dot(0, &label, matrice, previous, &removed, None);
+    for (k, step) in steps.iter().enumerate() {
+        let case = match step.case {
+            BitCase::Top => "top",
+            BitCase::Bottom => "bottom",
+            BitCase::Intermediate => "intermediate",
+        };
+        let mut label = format!("{case} case: {} dx{}", step.header, step.node);
+        if k + 1 == steps.len() {
+            label += &format!("\\n{integral}");
+        }
+        res += &step_to_dot(k + 1, &label, &step.matrice, previous, &removed, Some(step.node));
+        removed[step.node] = true;
+        previous = &step.matrice;
+    }
+    res
+}
+
+/// Formats the graph after one step of the resolution.
+fn step_to_dot(
+    k: usize,
+    label: &str,
+    matrice: &[Vec<i32>],
+    previous: &[Vec<i32>],
+    removed: &[bool],
+    node: Option<usize>,
+) -> String {
+    let mut res = format!("digraph step{k} {{\n    label=\"Step {k}: {label}\";\n");
+    for i in (0..matrice.len()).filter(|&i| !removed[i]) {
+        match node == Some(i) {
+            true => res += &format!("    x{i} [style=dashed, color=gray];\n"),
+            false => res += &format!("    x{i};\n"),
+        }
+    }
+    for (i, ligne) in matrice.iter().enumerate() {
+        for j in ligne.iter().enumerate().filter(|(_, &v)| v == 1).map(|(j, _)| j) {
+            match previous[i][j] == 1 {
+                true => res += &format!("    x{i} -> x{j};\n"),
+                false => res += &format!("    x{i} -> x{j} [style=bold];\n"),
+            }
+        }
+    }
+    res + "}\n"
+}
+
+/// Replaces the comments (`//`, `/* */` and lines starting with `#`) by spaces, keeping
+/// the newlines so that the line numbers do not change.
+fn strip_comments(source: &str) -> String {
+    let mut res = String::with_capacity(source.len());
+    let chars: Vec<char> = source.chars().collect();
+    let mut i = 0;
+    let mut in_string = false;
+    let mut line_start = true;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            if c == '\\' && i + 1 < chars.len() {
+                res.push(c);
+                res.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            in_string = c != '"';
+            res.push(c);
+        } else if c == '"' {
+            in_string = true;
+            res.push(c);
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') || c == '#' && line_start {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                res.push(if chars[i] == '\n' { '\n' } else { ' ' });
+                i += 1;
+            }
+            i += 2;
+            continue;
+        } else {
+            res.push(c);
+        }
+        if c == '\n' {
+            line_start = true;
+        } else if !c.is_whitespace() {
+            line_start = false;
+        }
+        i += 1;
+    }
+    res
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let source = strip_comments(source);
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '-' if chars.get(i + 1) == Some(&'>') => {
+                tokens.push((Token::Punct("->"), line));
+                i += 1;
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                tokens.push((Token::Punct("--"), line));
+                i += 1;
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' => {
+                let punct = ["{", "}", "[", "]", ";", ",", "=", ":"]
+                    .into_iter()
+                    .find(|p| p.starts_with(c))
+                    .unwrap();
+                tokens.push((Token::Punct(punct), line));
+            }
+            '"' => {
+                let start_line = line;
+                let mut value = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' && chars.get(i + 1) == Some(&'"') {
+                        i += 1;
+                    }
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    value.push(chars[i]);
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(format!("Line {start_line}: unterminated string"));
+                }
+                tokens.push((Token::Id(value), start_line));
+            }
+            '<' => {
+                // HTML string, only used in attributes
+                let start_line = line;
+                let mut depth = 0;
+                while i < chars.len() {
+                    match chars[i] {
+                        '<' => depth += 1,
+                        '>' => depth -= 1,
+                        '\n' => line += 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(format!("Line {start_line}: unterminated HTML string"));
+                }
+                tokens.push((Token::Id(String::new()), start_line));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let start = i;
+                while i + 1 < chars.len() && (chars[i + 1].is_alphanumeric() || chars[i + 1] == '_' || chars[i + 1] == '.') {
+                    i += 1;
+                }
+                tokens.push((Token::Id(chars[start..=i].iter().collect()), line));
+            }
+            c => return Err(format!("Line {line}: unexpected character '{c}'")),
+        }
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+struct DotParser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    poset: Poset,
+}
+
+impl DotParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.pos).or(self.tokens.last()) {
+            Some((_, line)) => *line,
+            None => 1,
+        }
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!("Line {}: {msg}", self.line())
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), String> {
+        if self.is_punct(punct) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{punct}'")))
+        }
+    }
+
+    fn id(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Id(id)) => {
+                let id = id.clone();
+                self.pos += 1;
+                Ok(id)
+            }
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    fn graph(&mut self) -> Result<(), String> {
+        if self.is_keyword("strict") {
+            self.pos += 1;
+        }
+        if self.is_keyword("graph") {
+            return Err(self.error("only directed graphs (digraph) are supported"));
+        }
+        if !self.is_keyword("digraph") {
+            return Err(self.error("expected 'digraph'"));
+        }
+        self.pos += 1;
+        if !self.is_punct("{") {
+            self.id()?;
+        }
+        self.expect("{")?;
+        self.statements()?;
+        self.expect("}")?;
+        if self.pos < self.tokens.len() {
+            return Err(self.error("unexpected text after the graph"));
+        }
+        Ok(())
+    }
+
+    /// Reads statements until the closing brace, returning the nodes they mention.
+    fn statements(&mut self) -> Result<BTreeSet<usize>, String> {
+        let mut nodes = BTreeSet::new();
+        while self.peek().is_some() && !self.is_punct("}") {
+            nodes.extend(self.statement()?);
+            if self.is_punct(";") || self.is_punct(",") {
+                self.pos += 1;
+            }
+        }
+        Ok(nodes)
+    }
+
+    fn statement(&mut self) -> Result<BTreeSet<usize>, String> {
+        // attribute statements: graph/node/edge [...] and a = b
+        if ["graph", "node", "edge"].iter().any(|k| self.is_keyword(k)) {
+            self.pos += 1;
+            self.attributes()?;
+            return Ok(BTreeSet::new());
+        }
+        if matches!(self.tokens.get(self.pos + 1), Some((Token::Punct("="), _))) {
+            self.id()?;
+            self.pos += 1;
+            self.id()?;
+            return Ok(BTreeSet::new());
+        }
+
+        let mut nodes = self.node_or_subgraph()?;
+        let mut all = nodes.clone();
+        while self.is_punct("->") || self.is_punct("--") {
+            if self.is_punct("--") {
+                return Err(self.error("undirected edge '--' in a digraph"));
+            }
+            self.pos += 1;
+            let targets = self.node_or_subgraph()?;
+            for &lower in &nodes {
+                for &upper in &targets {
+                    self.poset.add_relation(lower, upper);
+                }
+            }
+            all.extend(targets.iter().copied());
+            nodes = targets;
+        }
+        if self.is_punct("[") {
+            self.attributes()?;
+        }
+        Ok(all)
+    }
+
+    fn node_or_subgraph(&mut self) -> Result<BTreeSet<usize>, String> {
+        if self.is_keyword("subgraph") {
+            self.pos += 1;
+            if !self.is_punct("{") {
+                self.id()?;
+            }
+        }
+        if self.is_punct("{") {
+            self.pos += 1;
+            let nodes = self.statements()?;
+            self.expect("}")?;
+            return Ok(nodes);
+        }
+        let name = self.id()?;
+        // ports (node:port:compass) do not change the node
+        while self.is_punct(":") {
+            self.pos += 1;
+            self.id()?;
+        }
+        Ok(BTreeSet::from([self.poset.add_element(&name)]))
+    }
+
+    /// Skips attribute lists `[a=b, c=d][...]`.
+    fn attributes(&mut self) -> Result<(), String> {
+        while self.is_punct("[") {
+            self.pos += 1;
+            while !self.is_punct("]") {
+                self.id()?;
+                if self.is_punct("=") {
+                    self.pos += 1;
+                    self.id()?;
+                }
+                if self.is_punct(",") || self.is_punct(";") {
+                    self.pos += 1;
+                }
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::BigInt;
+
+    use super::{is_dot, matrice_to_dot, parse_dot, steps_to_dot};
+    use crate::integrator::IntegrationOptions;
+    use crate::resolution_using_lists::resolution_steps;
+
+    #[test]
+    fn test_parse_dot() {
+        let source = r#"
+            // build graph
+            strict digraph "build" {
+                rankdir=LR; node [shape=box, label=<<b>x</b>>]
+                "fetch deps" -> compile -> link [color=red];
+                compile:out -> test
+                /* the link step also
+                   packages the binary */
+                link -> package; docs
+                compile -> {lint "test"}; "fetch deps" -> test
+            }"#;
+        assert!(is_dot(source));
+        let poset = parse_dot(source).unwrap();
+        assert_eq!(poset.names(), ["fetch deps", "compile", "link", "test", "package", "docs", "lint"]);
+        assert_eq!(poset.variable_names()[0], "fetch_deps");
+        let options = IntegrationOptions {
+            quiet: true,
+            ..IntegrationOptions::default()
+        };
+        let le = poset.linear_extensions(&options).unwrap();
+        assert!(le.integral.contains("dfetch_deps"), "{}", le.integral);
+        // out-tree fetch deps < compile < {link < package, test, lint}: 6! / (6 * 5 * 2) = 12,
+        // times 7 places for docs
+        assert_eq!(le.count, BigInt::from(84));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(!is_dot("0 1\n0 0\n"));
+        assert_eq!(parse_dot("graph { a -- b }").unwrap_err(), "Line 1: only directed graphs (digraph) are supported");
+        assert_eq!(parse_dot("digraph {\n a -> \n}").unwrap_err(), "Line 3: expected a name");
+        assert_eq!(parse_dot("digraph {\n a -> b\n \"c").unwrap_err(), "Line 3: unterminated string");
+        assert_eq!(parse_dot("digraph { a -> b } c").unwrap_err(), "Line 1: unexpected text after the graph");
+        assert!(parse_dot("digraph { a -> b -> a }").unwrap().closure().is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        // 3 <- 1 -> 0 -> 2, the edge 1 -> 2 is redundant
+        let matrice = vec![vec![0, 0, 1, 0], vec![1, 0, 1, 1], vec![0, 0, 0, 0], vec![0, 0, 0, 0]];
+        let dot = matrice_to_dot(&matrice, "dag");
+        assert!(dot.starts_with("digraph dag {\n    x0;\n"), "{dot}");
+        let poset = parse_dot(&dot).unwrap();
+        assert_eq!(poset.names(), ["x0", "x1", "x2", "x3"]);
+        assert_eq!(
+            poset.covers().unwrap(),
+            vec![vec![0, 0, 1, 0], vec![1, 0, 0, 1], vec![0, 0, 0, 0], vec![0, 0, 0, 0]]
+        );
+
+        let matrice = vec![vec![0, 0, 1, 0], vec![1, 0, 0, 1], vec![0, 0, 0, 0], vec![0, 0, 0, 0]];
+        let (integral, steps) = resolution_steps(matrice.clone());
+        let dot = steps_to_dot(&matrice, &steps, &integral);
+        // the initial graph and one graph per step, each one readable
+        let graphs: Vec<&str> = dot.split_inclusive("}\n").collect();
+        assert_eq!(graphs.len(), 4);
+        assert!(graphs.iter().all(|graph| parse_dot(graph).is_ok()));
+        assert!(graphs[1].contains("intermediate case: Int_x1^x2 dx0"), "{}", graphs[1]);
+        assert!(graphs[1].contains("x0 [style=dashed, color=gray]"));
+        assert!(graphs[1].contains("x1 -> x2 [style=bold]"));
+        assert!(!graphs[2].contains("x0"));
+        assert!(graphs[3].contains(integral.as_ref().unwrap()));
+
+        // K2,2: the resolution stops on the initial graph, which shows the error
+        let matrice = vec![vec![0, 0, 1, 1], vec![0, 0, 1, 1], vec![0, 0, 0, 0], vec![0, 0, 0, 0]];
+        let (integral, steps) = resolution_steps(matrice.clone());
+        assert!(steps.is_empty());
+        let dot = steps_to_dot(&matrice, &steps, &integral);
+        assert!(dot.contains("initial graph\\nThe BIT resolution cannot decompose the DAG: 4 nodes"), "{dot}");
+        assert!(parse_dot(&dot).is_ok());
+    }
+}
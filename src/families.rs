@@ -0,0 +1,216 @@
+// This module builds the cover graphs of classical posets, whose numbers of linear extensions
+// are known: chains, antichains, fences, crowns, grids (products of two chains), Boolean
+// lattices, complete binary trees and Young diagrams. Unlike the random generators, the
+// nodes are numbered in a fixed order, so the same family and size always give the same
+// matrix; they serve as regression and scaling tests of the decomposition.
+
+use num::BigInt;
+
+use crate::maths::factorial;
+
+/// Chain `0 < 1 < ... < n-1`.
+pub fn chain(n: usize) -> Vec<Vec<i32>> {
+    let mut matrice = vec![vec![0; n]; n];
+    for i in 1..n {
+        matrice[i - 1][i] = 1;
+    }
+    matrice
+}
+
+/// `n` incomparable elements.
+pub fn antichain(n: usize) -> Vec<Vec<i32>> {
+    vec![vec![0; n]; n]
+}
+
+/// Fence (zigzag) of `n` elements: `0 < 1 > 2 < 3 > ...`.
+pub fn fence(n: usize) -> Vec<Vec<i32>> {
+    let mut matrice = vec![vec![0; n]; n];
+    for i in 1..n {
+        match i % 2 {
+            1 => matrice[i - 1][i] = 1,
+            _ => matrice[i][i - 1] = 1,
+        }
+    }
+    matrice
+}
+
+/// Crown of `2n` elements: a fence closed into a cycle, the minimal element `i` being below
+/// the maximal elements `n + i` and `n + (i + 1) % n`.
+pub fn crown(n: usize) -> Vec<Vec<i32>> {
+    let mut matrice = vec![vec![0; 2 * n]; 2 * n];
+    for i in 0..n {
+        matrice[i][n + i] = 1;
+        matrice[i][n + (i + 1) % n] = 1;
+    }
+    matrice
+}
+
+/// Young diagram of a shape: the cell `(r, c)` is below its right and lower neighbours.
+/// The cells are numbered row by row. A rectangle `a × b` is the grid, product of two chains.
+///
+/// # Arguments
+///
+/// * `shape` - The lengths of the rows, non increasing.
+///
+/// # Returns
+///
+/// The adjacency matrix of the cover graph, with `shape.iter().sum()` nodes.
+pub fn young(shape: &[usize]) -> Vec<Vec<i32>> {
+    let debuts: Vec<usize> = shape.iter().scan(0, |debut, &ligne| Some(std::mem::replace(debut, *debut + ligne))).collect();
+    let n = shape.iter().sum();
+    let mut matrice = vec![vec![0; n]; n];
+    for (r, &ligne) in shape.iter().enumerate() {
+        for c in 0..ligne {
+            let cellule = debuts[r] + c;
+            if c + 1 < ligne {
+                matrice[cellule][cellule + 1] = 1;
+            }
+            if r + 1 < shape.len() && c < shape[r + 1] {
+                matrice[cellule][debuts[r + 1] + c] = 1;
+            }
+        }
+    }
+    matrice
+}
+
+/// Grid `a × b`, product of two chains.
+pub fn grid(a: usize, b: usize) -> Vec<Vec<i32>> {
+    young(&vec![b; a])
+}
+
+/// Largest number of elements of the Boolean lattices generated by `GraphKind` (4096 nodes).
+pub const MAX_BOOLEAN_LATTICE: usize = 12;
+
+/// Largest depth of the binary trees generated by `GraphKind` (4095 nodes).
+pub const MAX_BINARY_TREE_DEPTH: usize = 12;
+
+/// Boolean lattice of the subsets of `n` elements, ordered by inclusion: the node `s` is the
+/// subset of the bits of `s`.
+pub fn boolean_lattice(n: usize) -> Vec<Vec<i32>> {
+    let taille = 1 << n;
+    let mut matrice = vec![vec![0; taille]; taille];
+    for (s, ligne) in matrice.iter_mut().enumerate() {
+        for k in (0..n).filter(|k| s & (1 << k) == 0) {
+            ligne[s | (1 << k)] = 1;
+        }
+    }
+    matrice
+}
+
+/// Complete binary tree of `depth` levels (`2^depth - 1` nodes), the root `0` below its
+/// children `2i + 1` and `2i + 2`.
+pub fn binary_tree(depth: usize) -> Vec<Vec<i32>> {
+    let n = (1 << depth) - 1;
+    let mut matrice = vec![vec![0; n]; n];
+    for i in 1..n {
+        matrice[(i - 1) / 2][i] = 1;
+    }
+    matrice
+}
+
+/// Staircase shape `(n, n-1, ..., 1)`.
+pub fn staircase(n: usize) -> Vec<usize> {
+    (1..=n).rev().collect()
+}
+
+/// Number of alternating permutations of `n` elements (Euler zigzag number), which is the
+/// number of linear extensions of the fence of `n` elements. Computed with the
+/// Seidel–Entringer triangle.
+pub fn euler_zigzag(n: usize) -> BigInt {
+    let mut ligne = vec![BigInt::from(1)];
+    for _ in 0..n {
+        let mut suivante = vec![BigInt::from(0)];
+        for k in 0..ligne.len() {
+            let valeur = &suivante[k] + &ligne[ligne.len() - 1 - k];
+            suivante.push(valeur);
+        }
+        ligne = suivante;
+    }
+    match n {
+        0 => BigInt::from(1),
+        _ => ligne.last().unwrap().clone(),
+    }
+}
+
+/// Number of standard Young tableaux of a shape (hook length formula), which is the number
+/// of linear extensions of its Young diagram.
+pub fn hook_length(shape: &[usize]) -> BigInt {
+    let mut produit = BigInt::from(1);
+    for (r, &ligne) in shape.iter().enumerate() {
+        for c in 0..ligne {
+            let dessous = shape[r + 1..].iter().filter(|&&l| l > c).count();
+            produit *= ligne - c + dessous;
+        }
+    }
+    factorial(shape.iter().sum::<usize>()) / produit
+}
+
+/// Number of linear extensions of the complete binary tree of `depth` levels: `n!` divided by
+/// the sizes of all the subtrees.
+pub fn binary_tree_extensions(depth: usize) -> BigInt {
+    let mut produit = BigInt::from(1);
+    for niveau in 1..=depth {
+        // the 2^(depth - niveau) subtrees of niveau levels
+        produit *= BigInt::from((1usize << niveau) - 1).pow(1 << (depth - niveau));
+    }
+    factorial((1usize << depth) - 1) / produit
+}
+
+/// Number of linear extensions of the Boolean lattices, known up to 4 elements (OEIS A046873).
+pub fn boolean_lattice_extensions(n: usize) -> Option<BigInt> {
+    [1u64, 1, 2, 48, 1680384].get(n).map(|&v| BigInt::from(v))
+}
+
+#[cfg(test)]
+mod tests {
+    use num::BigInt;
+
+    use super::*;
+    use crate::graph_io::EdgeList;
+
+    /// Counts the linear extensions by dynamic programming on the sets of elements placed.
+    fn extensions(matrice: &[Vec<i32>]) -> BigInt {
+        let n = matrice.len();
+        let predecesseurs: Vec<usize> =
+            (0..n).map(|j| (0..n).filter(|&i| matrice[i][j] == 1).fold(0, |masque, i| masque | 1 << i)).collect();
+        let mut compte = vec![BigInt::from(0); 1 << n];
+        compte[0] = BigInt::from(1);
+        for s in 0..1usize << n {
+            for j in (0..n).filter(|&j| s & (1 << j) == 0 && predecesseurs[j] & s == predecesseurs[j]) {
+                let valeur = compte[s].clone();
+                compte[s | 1 << j] += valeur;
+            }
+        }
+        compte[(1 << n) - 1].clone()
+    }
+
+    #[test]
+    fn test_families() {
+        assert_eq!(extensions(&chain(6)), BigInt::from(1));
+        assert_eq!(extensions(&antichain(5)), BigInt::from(120));
+        for n in 0..10 {
+            assert_eq!(extensions(&fence(n)), euler_zigzag(n), "fence {n}");
+        }
+        assert_eq!(euler_zigzag(7), BigInt::from(272));
+        // K2,2, and the crown of 6 elements
+        assert_eq!(extensions(&crown(2)), BigInt::from(4));
+        assert_eq!(EdgeList::from_matrice(&crown(4)).edges.len(), 8);
+        for (a, b) in [(2, 2), (2, 3), (3, 3), (3, 4)] {
+            assert_eq!(extensions(&grid(a, b)), hook_length(&vec![b; a]), "grid {a}x{b}");
+        }
+        assert_eq!(hook_length(&[3, 3, 3]), BigInt::from(42));
+        for shape in [staircase(4), vec![4, 2, 1], vec![3, 1, 1, 1]] {
+            assert_eq!(extensions(&young(&shape)), hook_length(&shape), "young {shape:?}");
+        }
+        for n in 0..5 {
+            assert_eq!(Some(extensions(&boolean_lattice(n))), boolean_lattice_extensions(n), "boolean {n}");
+        }
+        for depth in 1..5 {
+            assert_eq!(extensions(&binary_tree(depth)), binary_tree_extensions(depth), "binary tree {depth}");
+        }
+        assert_eq!(binary_tree_extensions(3), BigInt::from(80));
+        for matrice in [crown(5), grid(3, 4), boolean_lattice(3), young(&[4, 2, 1])] {
+            assert_eq!(EdgeList::from_matrice(&matrice).validate(), Ok(()));
+        }
+    }
+}
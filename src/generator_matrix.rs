@@ -1,10 +1,40 @@
 // This module provides utilities for generating directed acyclic graphs (DAGs)
-// with varying connectivity properties.
+// with varying connectivity properties: chains, out-trees and in-forests, Erdős–Rényi and
+// layered DAGs, posets of bounded width or height, random d-dimensional and graph orders.
 // It uses the `rand` crate for randomness, which facilitates the generation of random graphs.
+// The random generator is given by the caller: a run seeded with `rng_from_seed` can be
+// reproduced exactly.
 
 
-use rand::{Rng, thread_rng};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use num::BigInt;
+
+use crate::families;
+use crate::maths::factorial;
+use crate::transitive_reduction::covers;
+
+/// Draws a fresh seed, for the runs not given one.
+pub fn random_seed() -> u64 {
+    rand::random()
+}
+
+/// Creates the random generator of a seed.
+pub fn rng_from_seed(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Derives the seed of the `index`-th graph of a run from the seed of the run, so that each
+/// graph can be regenerated without the ones before it.
+pub fn sub_seed(seed: u64, index: u64) -> u64 {
+    // SplitMix64 finalizer, to spread consecutive indices
+    let mut z = seed ^ index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
 
 /// Generates a directed acyclic graph (DAG) where each node has exactly one outgoing edge,
 /// except for the last node which has none. This results in a simple linear chain of nodes.
@@ -12,6 +42,7 @@ use rand::seq::SliceRandom;
 /// # Arguments
 ///
 /// * `max_size` - The number of nodes in the generated graph.
+/// * `rng` - The random generator.
 ///
 /// # Returns
 ///
@@ -20,15 +51,16 @@ use rand::seq::SliceRandom;
 /// # Examples
 ///
 /// ```
-/// let matrix = one_arity_matrix_generator(5);
+/// use rustegrator::generator_matrix::{one_arity_matrix_generator, rng_from_seed};
+///
+/// let matrix = one_arity_matrix_generator(5, &mut rng_from_seed(42));
 /// assert_eq!(matrix.len(), 5);
 /// ```
-pub fn one_arity_matrix_generator(max_size: usize) -> Vec<Vec<i32>> {
+pub fn one_arity_matrix_generator<R: Rng + ?Sized>(max_size: usize, rng: &mut R) -> Vec<Vec<i32>> {
     let mut matrice = vec![vec![0; max_size]; max_size]; // Initialize the adjacency matrix.
-    let mut rng = thread_rng(); // Create a random number generator.
 
     let mut available_nodes: Vec<usize> = (0..max_size).collect();
-    available_nodes.shuffle(&mut rng); // Shuffle the array of available nodes.
+    available_nodes.shuffle(rng); // Shuffle the array of available nodes.
 
     // Connect each node to the next in the shuffled order, forming a simple path.
     for i in 0..max_size - 1 {
@@ -45,16 +77,18 @@ pub fn one_arity_matrix_generator(max_size: usize) -> Vec<Vec<i32>> {
 /// # Arguments
 ///
 /// * `matrice` - A mutable reference to the adjacency matrix of the existing graph.
+/// * `rng` - The random generator.
 ///
 /// # Examples
 ///
 /// ```
+/// use rustegrator::generator_matrix::{add_node, rng_from_seed};
+///
 /// let mut matrix = vec![vec![0; 3]; 3]; // A 3x3 matrix
-/// add_node(&mut matrix);
+/// add_node(&mut matrix, &mut rng_from_seed(42));
 /// assert_eq!(matrix.len(), 4); // The matrix should now be 4x4
 /// ```
-pub fn add_node(matrice: &mut Vec<Vec<i32>>) {
-    let mut rng = thread_rng(); // Create a random number generator.
+pub fn add_node<R: Rng + ?Sized>(matrice: &mut Vec<Vec<i32>>, rng: &mut R) {
     let size = matrice.len();
 
     // Extend each existing row by one column, initializing with 0.
@@ -66,7 +100,7 @@ pub fn add_node(matrice: &mut Vec<Vec<i32>>) {
     matrice.push(vec![0; size + 1]);
 
     // Select a random row index from the existing nodes to create an edge to the new node.
-    let random_row_index = rng.gen_range(0..size);
+    let random_row_index = rng.random_range(0..size);
     matrice[random_row_index][size] = 1;
 }
 
@@ -78,6 +112,7 @@ pub fn add_node(matrice: &mut Vec<Vec<i32>>) {
 /// # Arguments
 ///
 /// * `size` - The desired number of nodes in the graph.
+/// * `rng` - The random generator.
 ///
 /// # Returns
 ///
@@ -86,16 +121,577 @@ pub fn add_node(matrice: &mut Vec<Vec<i32>>) {
 /// # Examples
 ///
 /// ```
-/// let matrix = random_arity_matrix_generator(5);
+/// use rustegrator::generator_matrix::{random_arity_matrix_generator, rng_from_seed};
+///
+/// let matrix = random_arity_matrix_generator(5, &mut rng_from_seed(42));
 /// assert_eq!(matrix.len(), 5); // The matrix should have 5 rows
 /// ```
-pub fn random_arity_matrix_generator(size: usize) -> Vec<Vec<i32>> {
+pub fn random_arity_matrix_generator<R: Rng + ?Sized>(size: usize, rng: &mut R) -> Vec<Vec<i32>> {
     let mut matrice_res = vec![vec![0]]; // begins with a 1x1 matrix
     // Iteratively add nodes until the matrix reaches the desired size.
 
     for _ in 1..size {
-        add_node(&mut matrice_res);
+        add_node(&mut matrice_res, rng);
     }
 
     matrice_res
 }
+
+
+/// Relabels the nodes of a DAG by a random permutation, so that the indices do not give a
+/// topological order.
+fn melanger<R: Rng + ?Sized>(matrice: Vec<Vec<i32>>, rng: &mut R) -> Vec<Vec<i32>> {
+    let mut permutation: Vec<usize> = (0..matrice.len()).collect();
+    permutation.shuffle(rng);
+    let mut res = vec![vec![0; matrice.len()]; matrice.len()];
+    for (i, ligne) in matrice.iter().enumerate() {
+        for (j, &valeur) in ligne.iter().enumerate() {
+            res[permutation[i]][permutation[j]] = valeur;
+        }
+    }
+    res
+}
+
+/// Generates an Erdős–Rényi DAG: each pair of nodes is linked with probability `p`, the edge
+/// going along a random order of the nodes.
+///
+/// # Arguments
+///
+/// * `size` - The number of nodes.
+/// * `p` - The probability of each edge, between 0 and 1.
+/// * `rng` - The random generator.
+///
+/// # Returns
+///
+/// The adjacency matrix of the DAG.
+pub fn erdos_renyi_generator<R: Rng + ?Sized>(size: usize, p: f64, rng: &mut R) -> Vec<Vec<i32>> {
+    let mut matrice = vec![vec![0; size]; size];
+    for (i, ligne) in matrice.iter_mut().enumerate() {
+        for valeur in ligne.iter_mut().skip(i + 1) {
+            if rng.random_bool(p) {
+                *valeur = 1;
+            }
+        }
+    }
+    melanger(matrice, rng)
+}
+
+/// Generates a layered DAG: the edges go from a layer to the next one, each with
+/// probability `p`, and every node out of the first layer has at least one predecessor.
+///
+/// # Arguments
+///
+/// * `widths` - The number of nodes of each layer, from the sources.
+/// * `p` - The probability of each edge between consecutive layers.
+/// * `rng` - The random generator.
+///
+/// # Returns
+///
+/// The adjacency matrix of the DAG, with `widths.iter().sum()` nodes.
+pub fn layered_generator<R: Rng + ?Sized>(widths: &[usize], p: f64, rng: &mut R) -> Vec<Vec<i32>> {
+    let size = widths.iter().sum();
+    let mut matrice = vec![vec![0; size]; size];
+    let mut debut = 0;
+    for couches in widths.windows(2) {
+        let precedente = debut..debut + couches[0];
+        let suivante = debut + couches[0]..debut + couches[0] + couches[1];
+        for j in suivante {
+            for i in precedente.clone() {
+                if rng.random_bool(p) {
+                    matrice[i][j] = 1;
+                }
+            }
+            if !precedente.is_empty() && precedente.clone().all(|i| matrice[i][j] == 0) {
+                matrice[rng.random_range(precedente.clone())][j] = 1;
+            }
+        }
+        debut += couches[0];
+    }
+    melanger(matrice, rng)
+}
+
+/// Generates a random in-forest: every node but the roots has exactly one successor, so the
+/// edges go towards the roots of `trees` disjoint trees.
+///
+/// # Arguments
+///
+/// * `size` - The number of nodes.
+/// * `trees` - The number of trees (at least 1, at most `size`).
+/// * `rng` - The random generator.
+///
+/// # Returns
+///
+/// The adjacency matrix of the forest.
+pub fn in_forest_generator<R: Rng + ?Sized>(size: usize, trees: usize, rng: &mut R) -> Vec<Vec<i32>> {
+    let racines = trees.clamp(1, size.max(1)).min(size);
+    let mut matrice = vec![vec![0; size]; size];
+    // the first nodes are the roots, each other node points to a node before it
+    for i in racines..size {
+        matrice[i][rng.random_range(0..i)] = 1;
+    }
+    melanger(matrice, rng)
+}
+
+/// Generates a random poset of width at most `width`: the nodes, in a random order, are
+/// dealt to `width` chains, and the other pairs are linked along the order with
+/// probability `p`. The chains cover the poset, so it has no antichain of more than
+/// `width` elements.
+///
+/// # Arguments
+///
+/// * `size` - The number of nodes.
+/// * `width` - The maximal width (at least 1).
+/// * `p` - The probability of each edge out of the chains.
+/// * `rng` - The random generator.
+///
+/// # Returns
+///
+/// The adjacency matrix of a DAG whose transitive closure is the poset.
+pub fn bounded_width_generator<R: Rng + ?Sized>(size: usize, width: usize, p: f64, rng: &mut R) -> Vec<Vec<i32>> {
+    let mut matrice = vec![vec![0; size]; size];
+    let mut derniers: Vec<Option<usize>> = vec![None; width.max(1)];
+    for j in 0..size {
+        let chaine = rng.random_range(0..derniers.len());
+        if let Some(i) = derniers[chaine] {
+            matrice[i][j] = 1;
+        }
+        derniers[chaine] = Some(j);
+        for ligne in matrice.iter_mut().take(j) {
+            if rng.random_bool(p) {
+                ligne[j] = 1;
+            }
+        }
+    }
+    melanger(matrice, rng)
+}
+
+/// Generates a random poset of height at most `height`: each node gets a random level,
+/// and the pairs of nodes of increasing levels are linked with probability `p`, so that no
+/// chain has more than `height` elements.
+///
+/// # Arguments
+///
+/// * `size` - The number of nodes.
+/// * `height` - The maximal height (at least 1).
+/// * `p` - The probability of each edge.
+/// * `rng` - The random generator.
+///
+/// # Returns
+///
+/// The adjacency matrix of the DAG.
+pub fn bounded_height_generator<R: Rng + ?Sized>(size: usize, height: usize, p: f64, rng: &mut R) -> Vec<Vec<i32>> {
+    let niveaux: Vec<usize> = (0..size).map(|_| rng.random_range(0..height.max(1))).collect();
+    let mut matrice = vec![vec![0; size]; size];
+    for i in 0..size {
+        for j in 0..size {
+            if niveaux[i] < niveaux[j] && rng.random_bool(p) {
+                matrice[i][j] = 1;
+            }
+        }
+    }
+    melanger(matrice, rng)
+}
+
+/// Generates a random `dimension`-dimensional order: the intersection of `dimension`
+/// uniformly random permutations, `i < j` when `i` comes before `j` in all of them. With two
+/// permutations, this is the random 2-dimensional order, whose shape (width and height about
+/// `2√n`) is typical of large random posets, unlike the trees of
+/// `random_arity_matrix_generator`.
+///
+/// # Arguments
+///
+/// * `size` - The number of elements.
+/// * `dimension` - The number of permutations (1 gives a chain).
+/// * `rng` - The random generator.
+///
+/// # Returns
+///
+/// The adjacency matrix of the cover graph of the order.
+pub fn random_order_generator<R: Rng + ?Sized>(size: usize, dimension: usize, rng: &mut R) -> Vec<Vec<i32>> {
+    // rangs[k][i]: position of i in the k-th permutation
+    let rangs: Vec<Vec<usize>> = (0..dimension.max(1))
+        .map(|_| {
+            let mut permutation: Vec<usize> = (0..size).collect();
+            permutation.shuffle(rng);
+            permutation
+        })
+        .collect();
+    let ordre: Vec<Vec<bool>> =
+        (0..size).map(|i| (0..size).map(|j| i != j && rangs.iter().all(|rang| rang[i] < rang[j])).collect()).collect();
+    covers(&ordre)
+}
+
+/// Generates a random graph order: the transitive closure of a random graph on `0..size`
+/// whose pairs `i < j` are linked with probability `p`.
+///
+/// # Arguments
+///
+/// * `size` - The number of elements.
+/// * `p` - The probability of each edge of the graph, between 0 and 1.
+/// * `rng` - The random generator.
+///
+/// # Returns
+///
+/// The adjacency matrix of the cover graph of the order, its nodes in a random order.
+pub fn random_graph_order_generator<R: Rng + ?Sized>(size: usize, p: f64, rng: &mut R) -> Vec<Vec<i32>> {
+    let mut ordre = vec![vec![false; size]; size];
+    for i in (0..size).rev() {
+        for j in i + 1..size {
+            if rng.random_bool(p) && !ordre[i][j] {
+                // i gets below j and all that is above j
+                let au_dessus = ordre[j].clone();
+                ordre[i][j] = true;
+                for (i_k, &j_k) in ordre[i].iter_mut().zip(&au_dessus) {
+                    *i_k |= j_k;
+                }
+            }
+        }
+    }
+    melanger(covers(&ordre), rng)
+}
+
+/// Shape of the random DAGs produced by `GraphKind::generate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphKind {
+    /// A chain in a random order, see `one_arity_matrix_generator`
+    Chain,
+    /// A random tree, see `random_arity_matrix_generator`
+    Random,
+    /// An Erdős–Rényi DAG of edge probability `p`, see `erdos_renyi_generator`
+    ErdosRenyi { p: f64 },
+    /// A layered DAG, see `layered_generator`: the given layer widths, or layers of equal
+    /// widths (about the square root of the size) when `None`
+    Layered { widths: Option<Vec<usize>> },
+    /// A random in-forest of `trees` trees, see `in_forest_generator`
+    InForest { trees: usize },
+    /// A poset of width at most `width`, see `bounded_width_generator`
+    Width { width: usize },
+    /// A poset of height at most `height`, see `bounded_height_generator`
+    Height { height: usize },
+    /// A random `dimension`-dimensional order, see `random_order_generator`
+    Dimension { dimension: usize },
+    /// A random graph order of edge probability `p`, see `random_graph_order_generator`
+    GraphOrder { p: f64 },
+    /// `size` incomparable elements; this kind and the next ones are the deterministic
+    /// families of `families`, whose parameter is the size
+    Antichain,
+    /// A fence of `size` elements
+    Fence,
+    /// A crown of `2 * size` elements
+    Crown,
+    /// The grid `a × b`, or `size × size` when `None`
+    Grid { dimensions: Option<(usize, usize)> },
+    /// The Boolean lattice of the subsets of `size` elements
+    Boolean,
+    /// The complete binary tree of `size` levels
+    BinaryTree,
+    /// The Young diagram of a shape, or of the staircase `(size, ..., 1)` when `None`
+    Young { shape: Option<Vec<usize>> },
+}
+
+/// Probability of the edges added by the layered and bounded generators.
+const PROBABILITE_ARETE: f64 = 0.3;
+
+impl GraphKind {
+    /// Generates a DAG of `size` nodes (at least one) of this kind. A layered DAG of given
+    /// widths has their sum of nodes instead, and the families are generated with `size` as
+    /// parameter (a Boolean lattice of size 4 has 16 nodes). Fails when the Boolean lattice or
+    /// the binary tree would have more than a few thousand nodes, their number of nodes being
+    /// exponential in `size`.
+    pub fn generate<R: Rng + ?Sized>(&self, size: usize, rng: &mut R) -> Result<Vec<Vec<i32>>, String> {
+        let size = size.max(1);
+        self.check_size(size)?;
+        let matrice = match self {
+            GraphKind::Chain => one_arity_matrix_generator(size, rng),
+            GraphKind::Random => random_arity_matrix_generator(size, rng),
+            GraphKind::ErdosRenyi { p } => erdos_renyi_generator(size, *p, rng),
+            GraphKind::Layered { widths: Some(widths) } => layered_generator(widths, PROBABILITE_ARETE, rng),
+            GraphKind::Layered { widths: None } => {
+                let couches = (size as f64).sqrt().round().max(1.0) as usize;
+                let widths: Vec<usize> = (0..couches).map(|k| (k + 1) * size / couches - k * size / couches).collect();
+                layered_generator(&widths, PROBABILITE_ARETE, rng)
+            }
+            GraphKind::InForest { trees } => in_forest_generator(size, *trees, rng),
+            GraphKind::Width { width } => bounded_width_generator(size, *width, PROBABILITE_ARETE, rng),
+            GraphKind::Height { height } => bounded_height_generator(size, *height, PROBABILITE_ARETE, rng),
+            GraphKind::Dimension { dimension } => random_order_generator(size, *dimension, rng),
+            GraphKind::GraphOrder { p } => random_graph_order_generator(size, *p, rng),
+            GraphKind::Antichain => families::antichain(size),
+            GraphKind::Fence => families::fence(size),
+            GraphKind::Crown => families::crown(size),
+            GraphKind::Grid { dimensions: Some((a, b)) } => families::grid(*a, *b),
+            GraphKind::Grid { dimensions: None } => families::grid(size, size),
+            GraphKind::Boolean => families::boolean_lattice(size),
+            GraphKind::BinaryTree => families::binary_tree(size),
+            GraphKind::Young { shape: Some(shape) } => families::young(shape),
+            GraphKind::Young { shape: None } => families::young(&families::staircase(size)),
+        };
+        Ok(matrice)
+    }
+
+    /// Checks that `size` is at most `max_size`.
+    pub fn check_size(&self, size: usize) -> Result<(), String> {
+        match (self, self.max_size()) {
+            (GraphKind::Boolean, Some(max)) if size > max => {
+                Err(format!("The size of a boolean DAG is at most {max} (2^{max} nodes), found {size}"))
+            }
+            (GraphKind::BinaryTree, Some(max)) if size > max => {
+                Err(format!("The size of a binary-tree DAG is at most {max} (2^{max} - 1 nodes), found {size}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The largest size `generate` accepts: the Boolean lattices and the binary trees have a
+    /// number of nodes exponential in their size.
+    pub fn max_size(&self) -> Option<usize> {
+        match self {
+            GraphKind::Boolean => Some(families::MAX_BOOLEAN_LATTICE),
+            GraphKind::BinaryTree => Some(families::MAX_BINARY_TREE_DEPTH),
+            _ => None,
+        }
+    }
+
+    /// The number of linear extensions of the DAG generated for `size`, when it is known
+    /// without computing it: for the chains and the families but the crowns.
+    pub fn known_linear_extensions(&self, size: usize) -> Option<BigInt> {
+        let size = size.max(1);
+        match self {
+            GraphKind::Chain => Some(BigInt::from(1)),
+            GraphKind::Antichain => Some(factorial(size)),
+            GraphKind::Fence => Some(families::euler_zigzag(size)),
+            GraphKind::Grid { dimensions: Some((a, b)) } => Some(families::hook_length(&vec![*b; *a])),
+            GraphKind::Grid { dimensions: None } => Some(families::hook_length(&vec![size; size])),
+            GraphKind::Boolean => families::boolean_lattice_extensions(size),
+            GraphKind::BinaryTree if self.check_size(size).is_ok() => {
+                Some(families::binary_tree_extensions(size))
+            }
+            GraphKind::Young { shape: Some(shape) } => Some(families::hook_length(shape)),
+            GraphKind::Young { shape: None } => Some(families::hook_length(&families::staircase(size))),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for GraphKind {
+    type Err = String;
+
+    /// Reads `chain`, `random`, `erdos-renyi[:P]`, `layered[:W1,W2,...]`, `in-forest[:TREES]`,
+    /// `width:W`, `height:H`, `dimension[:D]`, `graph-order[:P]`, or a family: `antichain`, `fence`, `crown`, `grid[:AxB]`,
+    /// `boolean`, `binary-tree` or `young[:R1,R2,...]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (nom, parametre) = match s.split_once(':') {
+            Some((nom, parametre)) => (nom, Some(parametre)),
+            None => (s, None),
+        };
+        let entier = |parametre: &str| match parametre.trim().parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("Bad parameter in {s}: expected a positive integer, found '{parametre}'")),
+        };
+        let probabilite = |p: &str| match p.trim().parse::<f64>() {
+            Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+            _ => Err(format!("Bad parameter in {s}: expected a probability between 0 and 1")),
+        };
+        match (nom, parametre) {
+            ("chain", None) => Ok(GraphKind::Chain),
+            ("random", None) => Ok(GraphKind::Random),
+            ("erdos-renyi", None) => Ok(GraphKind::ErdosRenyi { p: 0.5 }),
+            ("erdos-renyi", Some(p)) => Ok(GraphKind::ErdosRenyi { p: probabilite(p)? }),
+            ("layered", None) => Ok(GraphKind::Layered { widths: None }),
+            ("layered", Some(widths)) => Ok(GraphKind::Layered {
+                widths: Some(widths.split(',').map(entier).collect::<Result<Vec<usize>, String>>()?),
+            }),
+            ("in-forest", None) => Ok(GraphKind::InForest { trees: 1 }),
+            ("in-forest", Some(trees)) => Ok(GraphKind::InForest { trees: entier(trees)? }),
+            ("width", Some(width)) => Ok(GraphKind::Width { width: entier(width)? }),
+            ("height", Some(height)) => Ok(GraphKind::Height { height: entier(height)? }),
+            ("dimension", None) => Ok(GraphKind::Dimension { dimension: 2 }),
+            ("dimension", Some(dimension)) => Ok(GraphKind::Dimension { dimension: entier(dimension)? }),
+            ("graph-order", None) => Ok(GraphKind::GraphOrder { p: 0.5 }),
+            ("graph-order", Some(p)) => Ok(GraphKind::GraphOrder { p: probabilite(p)? }),
+            ("antichain", None) => Ok(GraphKind::Antichain),
+            ("fence", None) => Ok(GraphKind::Fence),
+            ("crown", None) => Ok(GraphKind::Crown),
+            ("grid", None) => Ok(GraphKind::Grid { dimensions: None }),
+            ("grid", Some(dimensions)) => match dimensions.split_once('x') {
+                Some((a, b)) => Ok(GraphKind::Grid { dimensions: Some((entier(a)?, entier(b)?)) }),
+                None => Err(format!("Bad parameter in {s}: expected the dimensions AxB")),
+            },
+            ("boolean", None) => Ok(GraphKind::Boolean),
+            ("binary-tree", None) => Ok(GraphKind::BinaryTree),
+            ("young", None) => Ok(GraphKind::Young { shape: None }),
+            ("young", Some(shape)) => {
+                let shape = shape.split(',').map(entier).collect::<Result<Vec<usize>, String>>()?;
+                match shape.windows(2).all(|lignes| lignes[0] >= lignes[1]) {
+                    true => Ok(GraphKind::Young { shape: Some(shape) }),
+                    false => Err(format!("Bad parameter in {s}: the rows of a Young diagram cannot grow")),
+                }
+            }
+            _ => Err(format!(
+                "Unknown graph kind {s} (expected chain, random, erdos-renyi[:P], layered[:W1,W2,...], \
+                 in-forest[:TREES], width:W, height:H, dimension[:D], graph-order[:P], antichain, fence, crown, grid[:AxB], boolean, \
+                 binary-tree or young[:R1,R2,...])"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_io::EdgeList;
+    use crate::poset::Poset;
+
+    /// The poset of a DAG, its elements named after the node indices.
+    fn poset(matrice: &[Vec<i32>]) -> Poset {
+        let mut poset = Poset::new();
+        for i in 0..matrice.len() {
+            poset.add_element(&i.to_string());
+        }
+        for (i, j) in EdgeList::from_matrice(matrice).edges {
+            poset.add_relation(i, j);
+        }
+        poset
+    }
+
+    /// Size of the largest antichain and of the longest chain, by brute force.
+    fn largeur_hauteur(matrice: &[Vec<i32>]) -> (usize, usize) {
+        let n = matrice.len();
+        let closure = poset(matrice).closure().unwrap();
+        let comparables = |i: usize, j: usize| closure[i][j] || closure[j][i];
+        let mut largeur = 0;
+        for ensemble in 0u32..1 << n {
+            let elements: Vec<usize> = (0..n).filter(|&i| ensemble & (1 << i) != 0).collect();
+            if elements.iter().all(|&i| elements.iter().all(|&j| i == j || !comparables(i, j))) {
+                largeur = largeur.max(elements.len());
+            }
+        }
+        // longest chain ending at each node, in an order compatible with the closure
+        let mut ordre: Vec<usize> = (0..n).collect();
+        ordre.sort_by_key(|&j| (0..n).filter(|&i| closure[i][j]).count());
+        let mut hauteur = vec![1; n];
+        for &j in &ordre {
+            hauteur[j] = 1 + (0..n).filter(|&i| closure[i][j]).map(|i| hauteur[i]).max().unwrap_or(0);
+        }
+        (largeur, hauteur.into_iter().max().unwrap_or(0))
+    }
+
+    #[test]
+    fn test_erdos_renyi() {
+        let mut rng = rng_from_seed(1);
+        assert!(EdgeList::from_matrice(&erdos_renyi_generator(8, 0.0, &mut rng)).edges.is_empty());
+        let complet = EdgeList::from_matrice(&erdos_renyi_generator(8, 1.0, &mut rng));
+        assert_eq!(complet.edges.len(), 28);
+        assert_eq!(largeur_hauteur(&erdos_renyi_generator(8, 1.0, &mut rng)), (1, 8));
+        for _ in 0..20 {
+            assert_eq!(EdgeList::from_matrice(&erdos_renyi_generator(10, 0.4, &mut rng)).validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_layered() {
+        let mut rng = rng_from_seed(2);
+        for _ in 0..20 {
+            let matrice = layered_generator(&[3, 1, 4, 2], 0.3, &mut rng);
+            let graph = EdgeList::from_matrice(&matrice);
+            assert_eq!((graph.size, graph.validate()), (10, Ok(())));
+            // the nodes out of the first layer all have a predecessor
+            let sources = (0..10).filter(|&j| (0..10).all(|i| matrice[i][j] == 0)).count();
+            assert_eq!(sources, 3);
+            assert_eq!(largeur_hauteur(&matrice).1, 4);
+        }
+        assert_eq!(GraphKind::Layered { widths: None }.generate(9, &mut rng).unwrap().len(), 9);
+    }
+
+    #[test]
+    fn test_in_forest() {
+        let mut rng = rng_from_seed(3);
+        for trees in 1..4 {
+            let matrice = in_forest_generator(9, trees, &mut rng);
+            assert!(matrice.iter().all(|ligne| ligne.iter().sum::<i32>() <= 1));
+            let racines = matrice.iter().filter(|ligne| ligne.iter().sum::<i32>() == 0).count();
+            assert_eq!(racines, trees);
+            assert_eq!(poset(&matrice).components().len(), trees);
+        }
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut rng = rng_from_seed(4);
+        for borne in 1..4 {
+            for _ in 0..10 {
+                assert!(largeur_hauteur(&bounded_width_generator(10, borne, 0.2, &mut rng)).0 <= borne);
+                assert!(largeur_hauteur(&bounded_height_generator(10, borne, 0.5, &mut rng)).1 <= borne);
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_orders() {
+        let mut rng = rng_from_seed(7);
+        for _ in 0..10 {
+            for matrice in [random_order_generator(9, 2, &mut rng), random_graph_order_generator(9, 0.3, &mut rng)] {
+                // a cover graph: no edge implied by the others
+                assert_eq!(poset(&matrice).covers(), Ok(matrice.clone()));
+            }
+        }
+        // one permutation gives a chain, many give almost an antichain
+        assert_eq!(largeur_hauteur(&random_order_generator(8, 1, &mut rng)), (1, 8));
+        assert!(largeur_hauteur(&random_order_generator(8, 40, &mut rng)).0 >= 6);
+        assert_eq!(largeur_hauteur(&random_graph_order_generator(8, 1.0, &mut rng)), (1, 8));
+        assert_eq!(largeur_hauteur(&random_graph_order_generator(8, 0.0, &mut rng)), (8, 1));
+        // all the posets of 3 elements are 2-dimensional: the chain, V, Λ, chain + point and
+        // antichain shapes are all reached
+        let mut formes = std::collections::HashSet::new();
+        for _ in 0..200 {
+            let matrice = random_order_generator(3, 2, &mut rng);
+            let sources = (0..3).filter(|&j| matrice.iter().all(|ligne| ligne[j] == 0)).count();
+            formes.insert((largeur_hauteur(&matrice), EdgeList::from_matrice(&matrice).edges.len(), sources));
+        }
+        assert_eq!(formes.len(), 5, "{formes:?}");
+    }
+
+    #[test]
+    fn test_parse_kind() {
+        assert_eq!("erdos-renyi:0.25".parse(), Ok(GraphKind::ErdosRenyi { p: 0.25 }));
+        assert_eq!("layered:2,3".parse(), Ok(GraphKind::Layered { widths: Some(vec![2, 3]) }));
+        assert_eq!("in-forest".parse(), Ok(GraphKind::InForest { trees: 1 }));
+        assert_eq!("width:3".parse(), Ok(GraphKind::Width { width: 3 }));
+        assert!("erdos-renyi:2".parse::<GraphKind>().is_err());
+        assert!("height:0".parse::<GraphKind>().is_err());
+        assert!("width".parse::<GraphKind>().is_err());
+        assert_eq!("grid:2x3".parse(), Ok(GraphKind::Grid { dimensions: Some((2, 3)) }));
+        assert_eq!("young:3,1".parse(), Ok(GraphKind::Young { shape: Some(vec![3, 1]) }));
+        assert!("young:1,3".parse::<GraphKind>().is_err());
+        assert!("grid:2".parse::<GraphKind>().is_err());
+        assert_eq!("dimension".parse(), Ok(GraphKind::Dimension { dimension: 2 }));
+        assert_eq!("graph-order:0.1".parse(), Ok(GraphKind::GraphOrder { p: 0.1 }));
+    }
+
+    #[test]
+    fn test_families_by_name() {
+        let mut rng = rng_from_seed(5);
+        let kinds = ["antichain", "fence", "crown", "grid", "grid:2x5", "boolean", "binary-tree", "young", "young:4,2"];
+        let tailles: Vec<usize> = kinds
+            .iter()
+            .map(|kind| kind.parse::<GraphKind>().unwrap().generate(3, &mut rng).unwrap().len())
+            .collect();
+        assert_eq!(tailles, [3, 3, 6, 9, 10, 8, 7, 6, 6]);
+        // deterministic
+        assert_eq!(GraphKind::Crown.generate(4, &mut rng), GraphKind::Crown.generate(4, &mut rng_from_seed(6)));
+        assert_eq!("boolean".parse::<GraphKind>().unwrap().known_linear_extensions(3), Some(BigInt::from(48)));
+        assert_eq!(GraphKind::Crown.known_linear_extensions(3), None);
+
+        // the number of nodes is exponential in the size
+        assert_eq!(GraphKind::Boolean.generate(12, &mut rng).unwrap().len(), 4096);
+        assert_eq!(
+            GraphKind::Boolean.generate(64, &mut rng).unwrap_err(),
+            "The size of a boolean DAG is at most 12 (2^12 nodes), found 64"
+        );
+        assert_eq!(
+            GraphKind::BinaryTree.generate(30, &mut rng).unwrap_err(),
+            "The size of a binary-tree DAG is at most 12 (2^12 - 1 nodes), found 30"
+        );
+        assert_eq!(GraphKind::BinaryTree.known_linear_extensions(64), None);
+    }
+}
@@ -0,0 +1,517 @@
+// This module reads and writes DAGs in the formats used by the command line tools:
+// adjacency matrices (0/1 separated by spaces, or by commas in CSV files), sparse edge
+// lists (`u v` per line), JSON (`{"nodes": 4, "edges": [[0, 1], [1, 2]]}`) and Graphviz DOT.
+// The graphs are read as edge lists, so that large sparse DAGs never need an n×n matrix;
+// the parse errors give the line and the column of the faulty value.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::dot::{edges_to_dot, is_dot, parse_dot};
+
+/// A directed graph on the nodes `0..size`, given by its edges.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EdgeList {
+    pub size: usize,
+    /// Pairs `(source, target)`
+    pub edges: Vec<(usize, usize)>,
+}
+
+/// File formats of the graphs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    /// Adjacency matrices, one row per line, separated by blank lines
+    Matrix,
+    /// Adjacency matrices with comma separated values
+    Csv,
+    /// One edge `u v` per line, a single index for an isolated node
+    Edges,
+    /// `{"nodes": n, "edges": [[u, v], ...]}`, or a list of such objects, possibly in the
+    /// `graphs` field of an object
+    Json,
+    /// Graphviz `digraph`
+    Dot,
+}
+
+impl EdgeList {
+    /// Builds the edge list of an adjacency matrix.
+    pub fn from_matrice(matrice: &[Vec<i32>]) -> EdgeList {
+        let mut edges = Vec::new();
+        for (i, ligne) in matrice.iter().enumerate() {
+            edges.extend(ligne.iter().enumerate().filter(|(_, &v)| v == 1).map(|(j, _)| (i, j)));
+        }
+        EdgeList {
+            size: matrice.len(),
+            edges,
+        }
+    }
+
+    /// Builds the adjacency matrix of the graph.
+    pub fn to_matrice(&self) -> Vec<Vec<i32>> {
+        let mut matrice = vec![vec![0; self.size]; self.size];
+        for &(u, v) in &self.edges {
+            matrice[u][v] = 1;
+        }
+        matrice
+    }
+
+    /// Checks that the graph is a DAG: the nodes of the edges exist, there are no
+    /// self-loops, no duplicate edges and no cycles.
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or an error naming the faulty edge or a node on a cycle.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen = HashSet::new();
+        let mut in_degree = vec![0; self.size];
+        let mut successeurs = vec![Vec::new(); self.size];
+        for &(u, v) in &self.edges {
+            if u >= self.size || v >= self.size {
+                return Err(format!("The edge {u} -> {v} goes out of the {} nodes", self.size));
+            }
+            if u == v {
+                return Err(format!("Self-loop on node {u}"));
+            }
+            if !seen.insert((u, v)) {
+                return Err(format!("Duplicate edge {u} -> {v}"));
+            }
+            in_degree[v] += 1;
+            successeurs[u].push(v);
+        }
+        // Kahn's algorithm: the nodes never reached with a zero in-degree are on or after a cycle
+        let mut pile: Vec<usize> = (0..self.size).filter(|&i| in_degree[i] == 0).collect();
+        while let Some(u) = pile.pop() {
+            for &v in &successeurs[u] {
+                in_degree[v] -= 1;
+                if in_degree[v] == 0 {
+                    pile.push(v);
+                }
+            }
+        }
+        match (0..self.size).find(|&i| in_degree[i] > 0) {
+            Some(i) => Err(format!("The graph has a cycle through node {}", on_cycle(&successeurs, &in_degree, i))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Walks back from a node left by Kahn's algorithm until a node repeats, which is on a cycle.
+fn on_cycle(successeurs: &[Vec<usize>], in_degree: &[usize], start: usize) -> usize {
+    let mut predecesseur = vec![None; successeurs.len()];
+    for (u, succs) in successeurs.iter().enumerate().filter(|(u, _)| in_degree[*u] > 0) {
+        for &v in succs {
+            predecesseur[v] = Some(u);
+        }
+    }
+    let mut visite = HashSet::new();
+    let mut node = start;
+    while visite.insert(node) {
+        node = predecesseur[node].unwrap_or(node);
+    }
+    node
+}
+
+impl GraphFormat {
+    /// Guesses the format from the extension of a file (`.csv`, `.json`, `.dot`, `.gv`,
+    /// `.edges`, `.el`).
+    pub fn from_path(path: &Path) -> Option<GraphFormat> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(GraphFormat::Csv),
+            "json" => Some(GraphFormat::Json),
+            "dot" | "gv" => Some(GraphFormat::Dot),
+            "edges" | "el" => Some(GraphFormat::Edges),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the content. A first block of `n` lines of `n` values 0 or 1
+    /// is a matrix, other lines of numbers an edge list.
+    pub fn detect(content: &str) -> GraphFormat {
+        if is_dot(content) {
+            return GraphFormat::Dot;
+        }
+        let lignes: Vec<&str> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !is_comment(line))
+            .skip_while(|line| line.is_empty())
+            .take_while(|line| !line.is_empty())
+            .collect();
+        match lignes.first() {
+            Some(line) if line.starts_with('{') || line.starts_with('[') => GraphFormat::Json,
+            Some(line) if line.contains(',') => GraphFormat::Csv,
+            _ => {
+                let carree = lignes.iter().all(|line| {
+                    let valeurs: Vec<&str> = line.split_whitespace().collect();
+                    valeurs.len() == lignes.len() && valeurs.iter().all(|&v| v == "0" || v == "1")
+                });
+                match carree {
+                    true => GraphFormat::Matrix,
+                    false => GraphFormat::Edges,
+                }
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "matrix" => Ok(GraphFormat::Matrix),
+            "csv" => Ok(GraphFormat::Csv),
+            "edges" => Ok(GraphFormat::Edges),
+            "json" => Ok(GraphFormat::Json),
+            "dot" => Ok(GraphFormat::Dot),
+            _ => Err(format!("Unknown graph format {s} (expected matrix, csv, edges, json or dot)")),
+        }
+    }
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with("//") || line.starts_with('#')
+}
+
+/// Splits a line into its values with their column (from 1), on whitespace or on `sep`.
+fn values(line: &str, sep: Option<char>) -> Vec<(usize, &str)> {
+    let mut res = Vec::new();
+    let mut debut = None;
+    let est_sep = |c: char| match sep {
+        Some(sep) => c == sep,
+        None => c.is_whitespace(),
+    };
+    for (colonne, (i, c)) in line.char_indices().enumerate() {
+        match (est_sep(c), debut) {
+            (true, Some((col, start))) => {
+                res.push((col, line[start..i].trim()));
+                debut = None;
+            }
+            (true, None) if sep.is_some() => res.push((colonne + 1, "")),
+            (false, None) if !c.is_whitespace() => debut = Some((colonne + 1, i)),
+            _ => {}
+        }
+    }
+    match debut {
+        Some((col, start)) => res.push((col, line[start..].trim())),
+        None if sep.is_some() && line.ends_with(est_sep) => res.push((line.chars().count() + 1, "")),
+        None => {}
+    }
+    res
+}
+
+/// Parses the graphs of a text in the given format.
+///
+/// # Arguments
+///
+/// * `content` - The text to parse.
+/// * `format` - Its format.
+///
+/// # Returns
+///
+/// The graphs (not validated), or an error giving the line and the column of the first
+/// faulty value.
+pub fn parse_graphs(content: &str, format: GraphFormat) -> Result<Vec<EdgeList>, String> {
+    match format {
+        GraphFormat::Matrix => parse_matrices(content, None),
+        GraphFormat::Csv => parse_matrices(content, Some(',')),
+        GraphFormat::Edges => parse_edges(content).map(|graph| vec![graph]),
+        GraphFormat::Json => parse_json(content),
+        GraphFormat::Dot => {
+            let poset = parse_dot(content)?;
+            Ok(vec![EdgeList {
+                size: poset.len(),
+                edges: poset.relations().to_vec(),
+            }])
+        }
+    }
+}
+
+fn parse_matrices(content: &str, sep: Option<char>) -> Result<Vec<EdgeList>, String> {
+    let mut graphs = Vec::new();
+    let mut matrice: Vec<Vec<i32>> = Vec::new();
+    let mut debut = 0;
+    let mut fin_matrice = |matrice: &mut Vec<Vec<i32>>, debut: usize| -> Result<(), String> {
+        if !matrice.is_empty() {
+            if let Some(i) = matrice.iter().position(|ligne| ligne.len() != matrice.len()) {
+                return Err(format!(
+                    "Line {}: {} values in a matrix of {} rows (starting line {debut})",
+                    debut + i,
+                    matrice[i].len(),
+                    matrice.len()
+                ));
+            }
+            graphs.push(EdgeList::from_matrice(matrice));
+            matrice.clear();
+        }
+        Ok(())
+    };
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if is_comment(line.trim_start()) {
+            continue;
+        }
+        if line.trim().is_empty() {
+            fin_matrice(&mut matrice, debut)?;
+            continue;
+        }
+        if matrice.is_empty() {
+            debut = index + 1;
+        }
+        let ligne = values(line, sep)
+            .into_iter()
+            .map(|(colonne, v)| match v {
+                "0" => Ok(0),
+                "1" => Ok(1),
+                _ => Err(format!("Line {}, column {colonne}: expected 0 or 1, found '{v}'", index + 1)),
+            })
+            .collect::<Result<Vec<i32>, String>>()?;
+        matrice.push(ligne);
+    }
+    fin_matrice(&mut matrice, debut)?;
+    Ok(graphs)
+}
+
+fn parse_edges(content: &str) -> Result<EdgeList, String> {
+    let mut graph = EdgeList::default();
+    for (index, line) in content.lines().enumerate() {
+        if is_comment(line.trim_start()) {
+            continue;
+        }
+        let mut noeuds = Vec::new();
+        for (colonne, v) in values(line, None) {
+            if noeuds.len() == 2 {
+                return Err(format!("Line {}, column {colonne}: unexpected '{v}' (one edge `u v` per line)", index + 1));
+            }
+            let noeud: usize = v
+                .parse()
+                .map_err(|_| format!("Line {}, column {colonne}: expected a node index, found '{v}'", index + 1))?;
+            graph.size = graph.size.max(noeud + 1);
+            noeuds.push(noeud);
+        }
+        if let [u, v] = noeuds[..] {
+            graph.edges.push((u, v));
+        }
+    }
+    Ok(graph)
+}
+
+fn parse_json(content: &str) -> Result<Vec<EdgeList>, String> {
+    let value: Value =
+        serde_json::from_str(content).map_err(|e| format!("Line {}, column {}: {e}", e.line(), e.column()))?;
+    match value.get("graphs").unwrap_or(&value) {
+        Value::Array(graphs) => graphs.iter().enumerate().map(|(k, graph)| json_graph(graph, &format!("[{k}]"))).collect(),
+        graph => Ok(vec![json_graph(graph, "")?]),
+    }
+}
+
+/// Reads one JSON graph, `chemin` locating it in the document for the errors.
+fn json_graph(graph: &Value, chemin: &str) -> Result<EdgeList, String> {
+    let bad = |champ: &str, attendu: &str| format!("{chemin}{champ}: expected {attendu}");
+    let Value::Object(graph) = graph else {
+        return Err(bad("", "an object with nodes and edges"));
+    };
+    let mut res = EdgeList::default();
+    for (k, edge) in graph.get("edges").and_then(Value::as_array).ok_or_else(|| bad(".edges", "a list of edges"))?.iter().enumerate() {
+        let noeud = |i: usize| edge.get(i).and_then(Value::as_u64).map(|n| n as usize);
+        match (edge.as_array().map(Vec::len), noeud(0), noeud(1)) {
+            (Some(2), Some(u), Some(v)) => {
+                res.size = res.size.max(u.max(v) + 1);
+                res.edges.push((u, v));
+            }
+            _ => return Err(bad(&format!(".edges[{k}]"), "a pair of node indices [u, v]")),
+        }
+    }
+    match graph.get("nodes") {
+        None => {}
+        Some(nodes) => match nodes.as_u64() {
+            Some(n) if n as usize >= res.size => res.size = n as usize,
+            Some(n) => return Err(format!("{chemin}.nodes: {n} nodes, but the edges use node {}", res.size - 1)),
+            None => return Err(bad(".nodes", "a number of nodes")),
+        },
+    }
+    Ok(res)
+}
+
+/// Formats graphs in the given format. The matrices are separated by blank lines, several
+/// JSON graphs form a list and several DOT graphs follow each other (`dag1`, `dag2`...).
+/// An edge list holds one graph only, the next ones are separated by blank lines.
+///
+/// # Arguments
+///
+/// * `graphs` - The graphs to write.
+/// * `format` - The output format.
+///
+/// # Returns
+///
+/// The text, ended by a newline.
+pub fn format_graphs(graphs: &[EdgeList], format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Json => {
+            let values: Vec<Value> = graphs.iter().map(|graph| json!({"nodes": graph.size, "edges": graph.edges})).collect();
+            match &values[..] {
+                [value] => format!("{value}\n"),
+                values => format!("{}\n", Value::Array(values.to_vec())),
+            }
+        }
+        format => {
+            let textes: Vec<String> = graphs.iter().enumerate().map(|(k, graph)| format_graph(graph, format, k + 1)).collect();
+            match format {
+                GraphFormat::Dot => textes.concat(),
+                _ => textes.join("\n"),
+            }
+        }
+    }
+}
+
+/// Formats graphs generated from a seed, see `format_graphs`: the text formats start with a
+/// `// seed` comment, and the JSON form is `{"seed": s, "graphs": [...]}`.
+pub fn format_graphs_seeded(graphs: &[EdgeList], format: GraphFormat, seed: u64) -> String {
+    match format {
+        GraphFormat::Json => {
+            let graphs: Vec<Value> = graphs.iter().map(|graph| json!({"nodes": graph.size, "edges": graph.edges})).collect();
+            format!("{}\n", json!({"seed": seed, "graphs": graphs}))
+        }
+        format => format!("// seed {seed}\n{}", format_graphs(graphs, format)),
+    }
+}
+
+fn format_graph(graph: &EdgeList, format: GraphFormat, numero: usize) -> String {
+    let matrice = |sep: &str| {
+        let mut res = String::new();
+        for ligne in graph.to_matrice() {
+            let valeurs: Vec<String> = ligne.iter().map(|v| v.to_string()).collect();
+            res += &valeurs.join(sep);
+            res.push('\n');
+        }
+        res
+    };
+    match format {
+        GraphFormat::Matrix => matrice(" "),
+        GraphFormat::Csv => matrice(","),
+        GraphFormat::Edges => {
+            let mut res = String::new();
+            // the isolated nodes, for the edge list to keep all the nodes
+            let mut isoles = vec![true; graph.size];
+            for &(u, v) in &graph.edges {
+                isoles[u] = false;
+                isoles[v] = false;
+                res += &format!("{u} {v}\n");
+            }
+            for i in (0..graph.size).filter(|&i| isoles[i]) {
+                res += &format!("{i}\n");
+            }
+            res
+        }
+        GraphFormat::Json => format_graphs(std::slice::from_ref(graph), format),
+        GraphFormat::Dot => edges_to_dot(graph.size, &graph.edges, &format!("dag{numero}")),
+    }
+}
+
+/// Reads a whole file, or the standard input for `-`.
+pub fn read_source(chemin: &str) -> Result<String, String> {
+    match chemin {
+        "-" => io::read_to_string(io::stdin()).map_err(|e| format!("Cannot read stdin: {e}")),
+        chemin => fs::read_to_string(chemin).map_err(|e| format!("Cannot read {chemin}: {e}")),
+    }
+}
+
+/// Format of a file: the one given, else guessed from the extension then from the content.
+pub fn guess_format(chemin: &str, content: &str, format: Option<GraphFormat>) -> GraphFormat {
+    format
+        .or_else(|| GraphFormat::from_path(Path::new(chemin)))
+        .unwrap_or_else(|| GraphFormat::detect(content))
+}
+
+/// Parses and validates the graphs of a file already read.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file.
+/// * `chemin` - The file name, for the errors.
+/// * `format` - The format of the file, see `guess_format`.
+///
+/// # Returns
+///
+/// The DAGs, or an error prefixed by the file name.
+pub fn load_graphs(content: &str, chemin: &str, format: Option<GraphFormat>) -> Result<Vec<EdgeList>, String> {
+    let graphs = parse_graphs(content, guess_format(chemin, content, format)).map_err(|e| format!("{chemin}: {e}"))?;
+    for (k, graph) in graphs.iter().enumerate() {
+        graph.validate().map_err(|e| match graphs.len() {
+            1 => format!("{chemin}: {e}"),
+            _ => format!("{chemin}: graph {}: {e}", k + 1),
+        })?;
+    }
+    Ok(graphs)
+}
+
+/// Reads and validates the graphs of a file (`-` for the standard input), see `load_graphs`.
+pub fn read_graphs(chemin: &str, format: Option<GraphFormat>) -> Result<Vec<EdgeList>, String> {
+    load_graphs(&read_source(chemin)?, chemin, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_graphs, format_graphs_seeded, parse_graphs, EdgeList, GraphFormat};
+
+    fn chaine() -> EdgeList {
+        // 0 -> 2 -> 1, and 3 isolated
+        EdgeList {
+            size: 4,
+            edges: vec![(0, 2), (2, 1)],
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let formats = [GraphFormat::Matrix, GraphFormat::Csv, GraphFormat::Edges, GraphFormat::Json, GraphFormat::Dot];
+        for format in formats {
+            let texte = format_graphs(&[chaine()], format);
+            assert_eq!(GraphFormat::detect(&texte), format, "{texte}");
+            assert_eq!(parse_graphs(&texte, format), Ok(vec![chaine()]), "{texte}");
+        }
+        let deux = [chaine(), EdgeList { size: 1, edges: vec![] }];
+        for format in [GraphFormat::Matrix, GraphFormat::Csv, GraphFormat::Json] {
+            assert_eq!(parse_graphs(&format_graphs(&deux, format), format), Ok(deux.to_vec()));
+        }
+        for format in formats {
+            let texte = format_graphs_seeded(&[chaine()], format, 42);
+            assert!(texte.contains("seed") && texte.contains("42"), "{texte}");
+            assert_eq!(GraphFormat::detect(&texte), format, "{texte}");
+            assert_eq!(parse_graphs(&texte, format), Ok(vec![chaine()]), "{texte}");
+        }
+        let texte = format_graphs_seeded(&deux, GraphFormat::Json, 42);
+        assert_eq!(parse_graphs(&texte, GraphFormat::Json), Ok(deux.to_vec()));
+        assert_eq!(format_graphs(&[chaine()], GraphFormat::Edges), "0 2\n2 1\n3\n");
+        assert_eq!(format_graphs(&[chaine()], GraphFormat::Json), "{\"edges\":[[0,2],[2,1]],\"nodes\":4}\n");
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |content: &str, format: GraphFormat| parse_graphs(content, format).unwrap_err();
+        assert_eq!(err("// g\n0 1\n0 2", GraphFormat::Matrix), "Line 3, column 3: expected 0 or 1, found '2'");
+        assert_eq!(err("0 1\n0 0\n\n0 1\n0 0 1\n", GraphFormat::Matrix), "Line 5: 3 values in a matrix of 2 rows (starting line 4)");
+        assert_eq!(err("0,1\n0,,", GraphFormat::Csv), "Line 2, column 3: expected 0 or 1, found ''");
+        assert_eq!(err("0 1\n1 2 3", GraphFormat::Edges), "Line 2, column 5: unexpected '3' (one edge `u v` per line)");
+        assert_eq!(err("# edges\n0 1\n  1 b", GraphFormat::Edges), "Line 3, column 5: expected a node index, found 'b'");
+        assert_eq!(err("{\"edges\": [[0, 1],\n [1]]}", GraphFormat::Json), ".edges[1]: expected a pair of node indices [u, v]");
+        assert_eq!(err("[{\"nodes\": 1, \"edges\": [[0, 1]]}]", GraphFormat::Json), "[0].nodes: 1 nodes, but the edges use node 1");
+        assert!(err("{\"edges\": [[0, 1]\n  [1, 2]]}", GraphFormat::Json).starts_with("Line 2, column 3: "));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(chaine().validate(), Ok(()));
+        let graph = |size, edges: &[(usize, usize)]| EdgeList { size, edges: edges.to_vec() };
+        assert_eq!(graph(2, &[(0, 2)]).validate(), Err("The edge 0 -> 2 goes out of the 2 nodes".to_string()));
+        assert_eq!(graph(2, &[(1, 1)]).validate(), Err("Self-loop on node 1".to_string()));
+        assert_eq!(graph(2, &[(0, 1), (0, 1)]).validate(), Err("Duplicate edge 0 -> 1".to_string()));
+        // 0 -> 1 -> 2 -> 3 -> 1: node 0 is before the cycle
+        let err = graph(4, &[(0, 1), (1, 2), (2, 3), (3, 1)]).validate().unwrap_err();
+        assert!(["1", "2", "3"].iter().any(|i| err == format!("The graph has a cycle through node {i}")), "{err}");
+    }
+}
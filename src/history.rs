@@ -0,0 +1,267 @@
+// This module keeps the log of the integration runs: one JSON record per line with the
+// canonical hash of the integral, its result and the resources it took, so that past runs
+// of the same integral can be compared to spot performance regressions.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_HISTORY_FILE: &str = "historique.jsonl";
+
+// Serializes the appends of the worker threads of a batch
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+/// One integration run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Date of the run, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Hash of the canonical form of the integral (see `cache::canonical_hash`)
+    pub hash: String,
+    pub nbvars: usize,
+    /// The result as a fraction, `None` if the integration failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Largest number of monomials of the polynomial during the integration
+    pub peak_monomials: usize,
+    pub time_us: u64,
+    /// The result was found in the cache
+    #[serde(default)]
+    pub cached: bool,
+}
+
+impl RunRecord {
+    pub fn time(&self) -> Duration {
+        Duration::from_micros(self.time_us)
+    }
+}
+
+pub fn now_timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new<P: AsRef<Path>>(path: P) -> History {
+        History {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Appends a record at the end of the log.
+    pub fn append(&self, record: &RunRecord) -> Result<(), String> {
+        let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let _guard = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| format!("Cannot write history {}: {e}", self.path.display()))
+    }
+
+    /// Reads all the records of the log, in the order they were written. A missing log is empty.
+    pub fn records(&self) -> Result<Vec<RunRecord>, String> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("Cannot read history {}: {e}", self.path.display())),
+        };
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| format!("{}:{}: bad record: {e}", self.path.display(), i + 1))
+            })
+            .collect()
+    }
+}
+
+/// Selection of records, `None` meaning no constraint.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Prefix of the canonical hash
+    pub hash: Option<String>,
+    pub nbvars: Option<usize>,
+    /// Only the runs newer than this timestamp
+    pub since: Option<u64>,
+    pub failed_only: bool,
+}
+
+impl Filter {
+    pub fn matches(&self, record: &RunRecord) -> bool {
+        self.hash.as_ref().is_none_or(|prefix| record.hash.starts_with(prefix.as_str()))
+            && self.nbvars.is_none_or(|n| record.nbvars == n)
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && (!self.failed_only || record.error.is_some())
+    }
+}
+
+/// Latest run of an integral compared with the best of its previous runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub hash: String,
+    pub nbvars: usize,
+    pub runs: usize,
+    pub best: Duration,
+    pub latest: Duration,
+    /// `latest / best`
+    pub ratio: f64,
+    pub peak_monomials: (usize, usize),
+    /// The latest result differs from a previous one
+    pub result_changed: bool,
+}
+
+impl Comparison {
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        self.ratio > threshold || self.result_changed
+    }
+}
+
+/// Compares, for each integral computed successfully at least twice (cache hits excluded),
+/// its latest run with the fastest of the previous ones.
+pub fn compare(records: &[RunRecord]) -> Vec<Comparison> {
+    let mut runs: BTreeMap<&str, Vec<&RunRecord>> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.result.is_some() && !r.cached) {
+        runs.entry(&record.hash).or_default().push(record);
+    }
+    let mut res = Vec::new();
+    for (hash, mut runs) in runs {
+        runs.sort_by_key(|r| r.timestamp);
+        let Some((latest, previous)) = runs.split_last() else { continue };
+        let Some(best) = previous.iter().min_by_key(|r| r.time_us) else { continue };
+        res.push(Comparison {
+            hash: hash.to_string(),
+            nbvars: latest.nbvars,
+            runs: runs.len(),
+            best: best.time(),
+            latest: latest.time(),
+            ratio: latest.time_us as f64 / best.time_us.max(1) as f64,
+            peak_monomials: (best.peak_monomials, latest.peak_monomials),
+            result_changed: previous.iter().any(|r| r.result != latest.result),
+        });
+    }
+    res
+}
+
+/// Formats a timestamp as a UTC date `YYYY-MM-DD HH:MM:SS`.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (days, secs) = (timestamp / 86400, timestamp % 86400);
+    // civil date from the number of days since 1970-01-01 (H. Hinnant's algorithm)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::{compare, format_timestamp, Filter, History, RunRecord};
+
+    fn record(timestamp: u64, hash: &str, result: Option<&str>, time_us: u64) -> RunRecord {
+        RunRecord {
+            timestamp,
+            hash: hash.to_string(),
+            nbvars: 3,
+            result: result.map(|r| r.to_string()),
+            error: result.is_none().then(|| "Limit exceeded".to_string()),
+            peak_monomials: 10,
+            time_us,
+            cached: false,
+        }
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let path = env::temp_dir().join(format!("rustegrator-test-history-{}.jsonl", std::process::id()));
+        let history = History::new(&path);
+        assert_eq!(history.records(), Ok(vec![]));
+        let records = vec![record(1, "ab12", Some("1/6"), 100), record(2, "cd34", None, 5)];
+        for r in &records {
+            history.append(r).unwrap();
+        }
+        assert_eq!(history.records(), Ok(records));
+        assert!(!fs::read_to_string(&path).unwrap().contains("\"error\":null"));
+
+        fs::write(&path, "{\"timestamp\": 1}\n").unwrap();
+        let err = history.records().unwrap_err();
+        assert!(err.contains(":1: bad record"), "{err}");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_filter() {
+        let failed = record(20, "cd34", None, 5);
+        let filter = Filter {
+            hash: Some("cd".to_string()),
+            since: Some(10),
+            ..Filter::default()
+        };
+        assert!(filter.matches(&failed));
+        assert!(!filter.matches(&record(5, "cd34", None, 5)));
+        assert!(!filter.matches(&record(20, "ab12", None, 5)));
+        let filter = Filter {
+            failed_only: true,
+            ..Filter::default()
+        };
+        assert!(filter.matches(&failed));
+        assert!(!filter.matches(&record(20, "cd34", Some("1/2"), 5)));
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut cached = record(5, "ab12", Some("1/6"), 1);
+        cached.cached = true;
+        let records = vec![
+            record(1, "ab12", Some("1/6"), 100),
+            record(2, "ab12", Some("1/6"), 80),
+            cached,
+            record(4, "ab12", Some("1/6"), 200),
+            record(1, "cd34", Some("1/2"), 100),
+            record(2, "cd34", Some("1/3"), 90),
+            record(3, "ef56", Some("1/2"), 100),
+        ];
+        let comparisons = compare(&records);
+        assert_eq!(comparisons.len(), 2);
+        assert_eq!(comparisons[0].hash, "ab12");
+        assert_eq!(comparisons[0].runs, 3);
+        assert_eq!(comparisons[0].ratio, 2.5);
+        assert!(comparisons[0].is_regression(1.2));
+        assert!(!comparisons[0].result_changed);
+        assert!(comparisons[1].result_changed);
+        assert!(comparisons[1].is_regression(1.2));
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(951782400 + 3661), "2000-02-29 01:01:01");
+        assert_eq!(format_timestamp(1704067199), "2023-12-31 23:59:59");
+    }
+}
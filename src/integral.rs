@@ -1,5 +1,9 @@
 use std::collections::HashMap;
+use std::fmt;
 
+use num::BigInt;
+
+/// Bound of an integral: 0, 1 or another variable of the integral.
 #[derive(Debug, Clone)]
 pub enum Bound {
     Zero,
@@ -7,20 +11,21 @@ pub enum Bound {
     Var(String),
 }
 
-#[derive(Debug)]
+/// An iterated integral of 1, as given by `parser::parse`.
+#[derive(Debug, Clone, Default)]
 pub struct IntegralSpec {
+    /// Reference (index) of each variable name
     pub var_map: HashMap<String, usize>,
+    /// The integrations `(variable, lower bound, upper bound)`, the innermost one first
     pub elements: Vec<(usize, Bound, Bound)>,
 }
 
 impl IntegralSpec {
     pub fn new() -> IntegralSpec {
-        IntegralSpec {
-            var_map: HashMap::new(),
-            elements: vec![],
-        }
+        IntegralSpec::default()
     }
 
+    /// Returns the reference of a variable, registering it if it is new.
     pub fn register_var(&mut self, var_name: String) -> usize {
         // L'impression qu'on ajoute simplement une variable dans la hashMap à la fin
         let nb_vars = self.var_map.len();
@@ -49,3 +54,58 @@ impl IntegralSpec {
         self.elements.push((var_ref, b1, b2))
     }
 }
+
+/// An integral counted `weight` times in a sum of integrals, as given by `parser::parse_sum`.
+#[derive(Debug, Clone)]
+pub struct WeightedIntegral {
+    pub weight: BigInt,
+    pub spec: IntegralSpec,
+}
+
+impl WeightedIntegral {
+    pub fn new(weight: BigInt, spec: IntegralSpec) -> WeightedIntegral {
+        WeightedIntegral { weight, spec }
+    }
+}
+
+/// Prints a sum of integrals in the input syntax of `parser::parse_sum`:
+/// `2 * Int_0^1 ... + Int_0^1 ...`.
+pub fn format_sum(integrals: &[WeightedIntegral]) -> String {
+    let termes: Vec<String> = integrals.iter().map(|integral| integral.to_string()).collect();
+    termes.join(" + ")
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bound::Zero => write!(f, "0"),
+            Bound::One => write!(f, "1"),
+            Bound::Var(var) => write!(f, "{var}"),
+        }
+    }
+}
+
+/// Prints the specification back in the input syntax, so that `parser::parse`
+/// gives the same specification (with the same variable references).
+impl fmt::Display for IntegralSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (_, b1, b2) in self.elements.iter().rev() {
+            write!(f, "Int_{b1}^{b2} ")?;
+        }
+        write!(f, "1")?;
+        for (var, _, _) in self.elements.iter() {
+            write!(f, " d{}", self.var_name(*var))?;
+        }
+        Ok(())
+    }
+}
+
+/// The weight is omitted when it is 1.
+impl fmt::Display for WeightedIntegral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weight != BigInt::from(1) {
+            write!(f, "{} * ", self.weight)?;
+        }
+        write!(f, "{}", self.spec)
+    }
+}
@@ -1,24 +1,42 @@
 use crate::integral::Bound;
 use crate::integral::IntegralSpec;
+use crate::integral::WeightedIntegral;
+use crate::cache::{canonical_hash, Cache};
+use crate::history::{now_timestamp, History, RunRecord};
+use crate::debugger::{DebugOutcome, Debugger};
+use crate::checkpoint::{install_interrupt_handler, interrupted, Checkpoint};
+use crate::limits::{Limit, Limits};
+use crate::stats::{PolyStats, RunStats, StepStats};
 use num::{BigInt, BigRational, Signed};
+use std::cell::Cell;
 use std::collections::HashSet;
-use std::{fmt, process};
-use std::fmt::Binary;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fmt::Debug;
-use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
-use std::time::Instant;
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, ops::Mul};
-use std::fs::{File, OpenOptions};
-use crate::{abort, parser};
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use crate::maths::factorial;
+use crate::parser;
 
 // For now, we will use usize for degrees but maybe
 // this should be generic using the num crate
-#[derive(Debug)]
+/// A polynomial with rational coefficients, the monomials being indexed by the degree of
+/// each variable reference.
+#[derive(Debug, PartialEq)]
 pub struct Poly {
     nbvars: usize,
     monos: HashMap<Vec<i64>, BigRational>,
 }
 
+// Number of monomials integrated between two checks of the stop condition
+const STOP_POLL_PERIOD: usize = 1024;
+
 fn mk_one_mono(nbvars: usize) -> Vec<i64> {
     vec![0; nbvars]
 }
@@ -89,6 +107,7 @@ fn mono_subst_const(mono: &Vec<i64>, subst_var: usize) -> Vec<i64> {
 }
 
 impl Poly {
+    /// The constant polynomial 1 over `nbvars` variables.
     pub fn new(nbvars: usize) -> Poly {
         let mut monos = HashMap::new();
         monos.insert(
@@ -99,6 +118,10 @@ impl Poly {
     }
 
 
+    pub fn nbvars(&self) -> usize {
+        self.nbvars
+    }
+
     pub fn number_of_monos(&self) -> usize {
         self.monos.len()
     }
@@ -110,6 +133,75 @@ impl Poly {
         coefs.len()
     }
 
+    /// Computes the size measures of the polynomial (see `PolyStats`).
+    pub fn stats(&self) -> PolyStats {
+        let mut stats = PolyStats {
+            nb_monos: self.number_of_monos(),
+            nb_coefs: self.number_of_distinct_coefs(),
+            ..PolyStats::default()
+        };
+        for (mono, coef) in self.monos.iter() {
+            let degree: i64 = mono.iter().sum();
+            let numer_bits = coef.numer().bits();
+            let denom_bits = coef.denom().bits();
+            stats.max_degree = stats.max_degree.max(degree);
+            stats.max_numer_bits = stats.max_numer_bits.max(numer_bits);
+            stats.max_denom_bits = stats.max_denom_bits.max(denom_bits);
+            // key (Vec header + degrees), two BigInt (header + digits) and the hashmap control byte
+            stats.memory_bytes += 24 + 8 * mono.len() + 2 * 32 + 8 * (numer_bits.div_ceil(64) + denom_bits.div_ceil(64)) as usize + 1;
+        }
+        stats
+    }
+
+    /// Serializes the polynomial in a line-based text format: a header line
+    /// `poly <nbvars> <nbmonos>` followed by one line `<coef> <d1> ... <dn>` per monomial.
+    pub fn serialize(&self) -> String {
+        let mut res = format!("poly {} {}\n", self.nbvars, self.monos.len());
+        let mut keys: Vec<&Vec<i64>> = self.monos.keys().collect();
+        keys.sort();
+        for mono in keys {
+            res.push_str(&self.monos[mono].to_string());
+            for d in mono {
+                res.push(' ');
+                res.push_str(&d.to_string());
+            }
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Reads back a polynomial written by `serialize`.
+    pub fn deserialize(source: &str) -> Result<Poly, String> {
+        let mut lines = source.lines();
+        let header: Vec<&str> = lines.next().unwrap_or("").split_whitespace().collect();
+        let (nbvars, nbmonos) = match header.as_slice() {
+            ["poly", nbvars, nbmonos] => match (nbvars.parse::<usize>(), nbmonos.parse::<usize>()) {
+                (Ok(nbvars), Ok(nbmonos)) => (nbvars, nbmonos),
+                _ => return Err("Bad polynomial header".to_string()),
+            },
+            _ => return Err("Missing polynomial header".to_string()),
+        };
+        let mut monos = HashMap::new();
+        for (num, line) in lines.enumerate() {
+            let mut fields = line.split_whitespace();
+            let coef = match fields.next().map(|c| c.parse::<BigRational>()) {
+                Some(Ok(coef)) => coef,
+                _ => return Err(format!("Bad coefficient in monomial {}", num + 1)),
+            };
+            let mono: Result<Vec<i64>, _> = fields.map(|d| d.parse::<i64>()).collect();
+            match mono {
+                Ok(mono) if mono.len() == nbvars => {
+                    monos.insert(mono, coef);
+                }
+                _ => return Err(format!("Bad degrees in monomial {}", num + 1)),
+            }
+        }
+        if monos.len() != nbmonos {
+            return Err(format!("Expected {} monomials, found {}", nbmonos, monos.len()));
+        }
+        Ok(Poly { nbvars, monos })
+    }
+
     pub fn is_constant(self) -> bool {
         if self.monos.len() != 1 {
             return false;
@@ -127,21 +219,36 @@ impl Poly {
         if self.monos.len() != 1 {
             return None;
         }
-        for mono in self.monos {
-            for vdeg in mono.0 {
-                if vdeg > 0 {
-                    return None;
-                } else {
-                    return Some(mono.1);
-                }
-            }
+        let mono = self.monos.into_iter().next()?;
+        if mono.0.iter().any(|&vdeg| vdeg > 0) {
+            None
+        } else {
+            Some(mono.1)
         }
-        return None;
     }
 
-    pub fn integrate(self, spec: &IntegralSpec, var: usize, from: &Bound, to: &Bound) -> Poly {
+    /// Integrates the polynomial in the variable `var` between `from` and `to`.
+    pub fn integrate(&self, spec: &IntegralSpec, var: usize, from: &Bound, to: &Bound) -> Poly {
+        self.integrate_until(spec, var, from, to, &|_| false)
+            .expect("integration step cannot be stopped")
+    }
+
+    /// Same as `integrate` but `stop` is polled regularly during the step with the number
+    /// of monomials produced so far: when it returns true the step is abandoned and `None`
+    /// is returned (`self` is left untouched).
+    pub fn integrate_until(
+        &self,
+        spec: &IntegralSpec,
+        var: usize,
+        from: &Bound,
+        to: &Bound,
+        stop: &dyn Fn(usize) -> bool,
+    ) -> Option<Poly> {
         let mut nmonos: HashMap<Vec<i64>, BigRational> = HashMap::new();
-        for (mut mono, coef) in self.monos.iter() {
+        for (count, (mono, coef)) in self.monos.iter().enumerate() {
+            if count % STOP_POLL_PERIOD == STOP_POLL_PERIOD - 1 && stop(nmonos.len()) {
+                return None;
+            }
             let amono = antideriv_mono(mono, var);
             let acoef = antideriv_coef(coef, mono, var);
 
@@ -184,92 +291,26 @@ impl Poly {
         };
         //print!("{}", res);
 
-        return res;
+        Some(res)
     }
 
-    /**
-     * Debugger
-     **/
-    pub fn integrateDebugger(
-        self,
-        spec: &IntegralSpec,
-        var: usize,
-        from: &Bound,
-        to: &Bound,
-    ) -> Poly {
-        let mut temps_perdu = Instant::now() - Instant::now();
-        println!("{}", self);
-        let now = Instant::now();
-        let mut nmonos: HashMap<Vec<i64>, BigRational> = HashMap::new();
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .open("historique.txt")
-            .unwrap();
+    /// Evaluates the polynomial, `values` giving the value of each variable reference.
+    /// Variables without value are only allowed if they do not occur in the polynomial.
+    pub fn eval(&self, spec: &IntegralSpec, values: &HashMap<usize, BigRational>) -> Result<BigRational, String> {
+        let mut res = BigRational::from_integer(BigInt::from(0));
         for (mono, coef) in self.monos.iter() {
-            println!("{}", mono_pp(spec, mono));
-            println!("{}", "appuyez sur une touche pour continuer, entrez markdown pour ecrire markdown dans le fichier historique");
-            let mut input = String::new();
-            let tmp = Instant::now();
-            io::stdin()
-                .read_line(&mut input)
-                .expect("Échec de la lecture de la ligne");
-            let trimmed_input = input.trim();
-            match trimmed_input {
-                "markdown" => if let Err(e) = writeln!(file,"markdown"){
-                    eprintln!("Probleme pour écrire sur le fichier {}",e);
-                }
-                _ => println!("On Continue"),
-            }
-            temps_perdu += tmp.elapsed();
-            print!("\n");
-            let amono = antideriv_mono(mono, var);
-            let acoef = antideriv_coef(coef, mono, var);
-            let lmono: Option<Vec<i64>> = match &to {
-                Bound::Zero => None,
-                Bound::One => Some(mono_subst_const(&amono, var)),
-                Bound::Var(by_var) => {
-                    Some(mono_subst_var(&amono, var, spec.var_ref(by_var.clone())))
+            let mut term = coef.clone();
+            for (var_ref, &d) in mono.iter().enumerate() {
+                if d > 0 {
+                    match values.get(&var_ref) {
+                        Some(value) => term *= value.pow(d as i32),
+                        None => return Err(format!("No value for {}", spec.var_name(var_ref))),
+                    }
                 }
-            };
-            if lmono.is_some() {
-                let llmono = lmono.unwrap();
-                let entry = nmonos
-                    .entry(llmono)
-                    .or_insert(BigRational::from_integer(BigInt::from(0)));
-                *entry += &acoef;
-                print!("")
             }
-
-            let rmono: Option<Vec<i64>> = match &from {
-                Bound::Zero => None,
-                Bound::One => Some(mono_subst_const(&amono, var)),
-                Bound::Var(by_var) => {
-                    Some(mono_subst_var(&amono, var, spec.var_ref(by_var.clone())))
-                }
-            };
-
-            if rmono.is_some() {
-                let rrmono = rmono.unwrap();
-                let entry = nmonos
-                    .entry(rrmono)
-                    .or_insert(BigRational::from_integer(BigInt::from(0)));
-                *entry += -acoef;
-            }
-            //print!("{}", self.nbvars);
-        }
-        let res = Poly {
-            nbvars: self.nbvars,
-            monos: nmonos,
-        };
-        //print!("{}", res);
-        print!("\n");
-        let end = now.elapsed() - temps_perdu;
-        println!("Temps écoulé: {:.10?}", end);
-        if let Err(e) = writeln!(file,"{:?}", end){
-            eprintln!("Probleme pour écrire sur le fichier {}",e);
+            res += term;
         }
-        return res;
+        Ok(res)
     }
 }
 
@@ -351,114 +392,439 @@ pub fn poly_pp(spec: &IntegralSpec, poly: &Poly) -> String {
 
 
 
-/**
-    We integrate using an integral from a text val, we give it as a spec after using the parser's
-    function previously
-**/
+/// Options of an integration run (see `integrate_spec`).
+#[derive(Debug, Clone, Default)]
+pub struct IntegrationOptions {
+    /// Only show the end result
+    pub quiet: bool,
+    /// Show the formula at each integration step
+    pub formula: bool,
+    /// Show the statistics table at the end of the integration
+    pub stats: bool,
+    /// Write the statistics of each step to this CSV file
+    pub stats_csv: Option<String>,
+    /// Periodically save the integration state to this file (and on Ctrl-C)
+    pub checkpoint: Option<String>,
+    /// Minimum time between two checkpoints
+    pub checkpoint_interval: Duration,
+    /// Resource limits, checked between and during the integration steps
+    pub limits: Limits,
+    /// Run the integration in the interactive step debugger
+    pub debug: bool,
+    /// Directory of the result cache (`None` to disable it)
+    pub cache: Option<PathBuf>,
+    /// Run log where a record is appended after each integration (`None` to disable it)
+    pub history: Option<PathBuf>,
+}
 
-pub fn integrate_file(spec: &IntegralSpec){
-    let mut poly = Poly::new(spec.elements.len());
-    let mut step = 1;
-    let mut file = OpenOptions::new()
-        .write(true)
-        .append(true)
-        .create(true)
-        .open("historique.txt")
-        .expect("Impossible d'ouvrir le fichier historique.txt");
-    let debut = Instant::now();
-    for (var, from, to) in spec.elements.iter() {
-        poly = poly.integrate(spec, *var, from, to);
-        step += 1;
-    }
-    let end = Instant::now();
-    let time_passed = end.duration_since(debut);
-    writeln!(file, "{:?}", time_passed)
-        .expect("Unable to write to file");
-    println!("{:?}", time_passed);
-    file.flush().expect("Unable to flush file");
+/// Error of an integration run.
+#[derive(Debug)]
+pub enum IntegrationError {
+    Failed(String),
+    /// A resource limit was exceeded at `step`, `stats` describing the last polynomial computed
+    LimitExceeded {
+        limit: Limit,
+        step: usize,
+        var: String,
+        stats: PolyStats,
+    },
 }
 
+impl fmt::Display for IntegrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrationError::Failed(msg) => write!(f, "{msg}"),
+            IntegrationError::LimitExceeded { limit, step, var, stats } => write!(
+                f,
+                "Limit exceeded ({limit}) at step {step} (d{var}), last polynomial: #monomials={} #coefficients={} max_degree={} numer_bits={} denom_bits={} memory={}B",
+                stats.nb_monos,
+                stats.nb_coefs,
+                stats.max_degree,
+                stats.max_numer_bits,
+                stats.max_denom_bits,
+                stats.memory_bytes
+            ),
+        }
+    }
+}
 
+impl std::error::Error for IntegrationError {}
 
+impl From<String> for IntegrationError {
+    fn from(msg: String) -> Self {
+        IntegrationError::Failed(msg)
+    }
+}
+
+/// Computes the value of an integral, using the cache and writing the run log as set
+/// in `options`.
 pub fn integrate_spec(
     spec: &IntegralSpec,
-    quiet_mode: bool,
-    formula_mode: bool,
-    stats_mode: bool,
-    //debug: bool, unused
-) -> Result<BigRational, String> {
-    let mut poly = Poly::new(spec.elements.len());
-    let mut step = 1;
-            for (var, from, to) in spec.elements.iter() {
-                if !quiet_mode {
+    options: &IntegrationOptions,
+) -> Result<BigRational, IntegrationError> {
+    let now = Instant::now();
+    let cache = options.cache.as_ref().filter(|_| !options.debug).map(Cache::new);
+    if let Some(entry) = cache.as_ref().and_then(|cache| cache.get(spec)) {
+        if !options.quiet {
+            println!("Result found in the cache (computed in {:?})", entry.time);
+        }
+        record_run(spec, options, &Ok((entry.result.clone(), 0)), now.elapsed(), true);
+        return Ok(entry.result);
+    }
+    let res = integrate_spec_from(spec, Poly::new(spec.elements.len()), 0, options);
+    let time = now.elapsed();
+    if let (Some(cache), Ok((res, _))) = (cache, &res) {
+        if let Err(e) = cache.put(spec, res, time) {
+            eprintln!("Warning: {e}");
+        }
+    }
+    record_run(spec, options, &res, time, false);
+    res.map(|(res, _)| res)
+}
+
+/// Computes the value of a weighted sum of integrals (see `parser::parse_sum`), each one
+/// with `integrate_spec`.
+pub fn integrate_sum(
+    integrals: &[WeightedIntegral],
+    options: &IntegrationOptions,
+) -> Result<BigRational, IntegrationError> {
+    let mut res = BigRational::from_integer(BigInt::from(0));
+    for integral in integrals {
+        res += integrate_spec(&integral.spec, options)? * BigRational::from_integer(integral.weight.clone());
+    }
+    Ok(res)
+}
+
+/// Number of variables of a sum of integrals, which all integrate the same variables.
+pub(crate) fn sum_nbvars(integrals: &[WeightedIntegral]) -> usize {
+    integrals.first().map_or(0, |integral| integral.spec.var_map.len())
+}
+
+/// Appends the outcome of an integration to the run log (not in debug mode, where the
+/// time is mostly spent waiting for the user).
+fn record_run(
+    spec: &IntegralSpec,
+    options: &IntegrationOptions,
+    res: &Result<(BigRational, usize), IntegrationError>,
+    time: Duration,
+    cached: bool,
+) {
+    let Some(path) = options.history.as_ref().filter(|_| !options.debug) else { return };
+    let (result, error, peak_monomials) = match res {
+        Ok((res, peak)) => (Some(res.to_string()), None, *peak),
+        Err(e @ IntegrationError::LimitExceeded { stats, .. }) => (None, Some(e.to_string()), stats.nb_monos),
+        Err(e) => (None, Some(e.to_string()), 0),
+    };
+    let record = RunRecord {
+        timestamp: now_timestamp(),
+        hash: canonical_hash(spec),
+        nbvars: spec.elements.len(),
+        result,
+        error,
+        peak_monomials,
+        time_us: time.as_micros() as u64,
+        cached,
+    };
+    if let Err(e) = History::new(path).append(&record) {
+        eprintln!("Warning: {e}");
+    }
+}
+
+/// Resumes the integration saved in the checkpoint file `path`.
+/// Returns the result with the number of variables used for `#le`.
+pub fn resume_spec(path: &str, options: &IntegrationOptions) -> Result<(BigRational, usize), IntegrationError> {
+    let ckpt = Checkpoint::load(path)?;
+    if !options.quiet {
+        println!("Resuming {} at step {}", ckpt.spec, ckpt.step + 1);
+    }
+    let (res, _) = integrate_spec_from(&ckpt.spec, ckpt.poly, ckpt.step, options)?;
+    Ok((res, ckpt.spec.var_map.len()))
+}
+
+/// Integrates `poly` along the elements of `spec`, starting after the `first_step` already done.
+/// Returns the result with the largest number of monomials reached.
+fn integrate_spec_from(
+    spec: &IntegralSpec,
+    mut poly: Poly,
+    first_step: usize,
+    options: &IntegrationOptions,
+) -> Result<(BigRational, usize), IntegrationError> {
+    if options.debug {
+        let debugger = Debugger::new(spec, poly, first_step);
+        return match debugger.run(&mut io::stdin().lock(), &mut io::stdout()) {
+            Ok(DebugOutcome::Finished(poly)) => match poly.as_constant() {
+                Some(res) => Ok((res, 0)),
+                None => Err("Stuck integral".to_string().into()),
+            },
+            Ok(DebugOutcome::Quit) => Err("Stopped in the debugger".to_string().into()),
+            Err(e) => Err(e.into()),
+        };
+    }
+    let collect_stats = options.stats || options.stats_csv.is_some();
+    let limited = !options.limits.is_unlimited();
+    let mut run_stats = RunStats::new();
+    let start = Instant::now();
+    let mut last_checkpoint = Instant::now();
+    if options.checkpoint.is_some() {
+        install_interrupt_handler();
+    }
+    let mut poly_stats = if limited { poly.stats() } else { PolyStats::default() };
+    let mut peak_monos = poly.number_of_monos();
+            for (index, (var, from, to)) in spec.elements.iter().enumerate().skip(first_step) {
+                let step = index + 1;
+                if !options.quiet {
                     println!("Step {step}:");
-                    if formula_mode {
+                    if options.formula {
                         println!("  {:?}", poly_pp(spec, &poly));
                     }
-                    if stats_mode {
-                        let nb_monos = &poly.number_of_monos();
-                        let nb_coefs = &poly.number_of_distinct_coefs();
-                        println!("  #monomials={nb_monos}   #coefficients={nb_coefs}");
+                }
+                    let now = Instant::now();
+                    let limit_hit = Cell::new(None);
+                    // the new monomials are assumed to be as large as the current ones
+                    let bytes_per_mono = poly_stats.memory_bytes / poly_stats.nb_monos.max(1);
+                    let stop = |nb_new_monos: usize| {
+                        if options.checkpoint.is_some() && interrupted() {
+                            return true;
+                        }
+                        let memory = poly_stats.memory_bytes + nb_new_monos * bytes_per_mono;
+                        limit_hit.set(options.limits.check(nb_new_monos, memory, start.elapsed()));
+                        limit_hit.get().is_some()
+                    };
+                    poly = match poly.integrate_until(spec, *var, from, to, &stop) {
+                        Some(npoly) => npoly,
+                        None => match limit_hit.get() {
+                            Some(limit) => return Err(limit_exceeded(spec, step, *var, limit, poly_stats)),
+                            None => return Err(interrupt(spec, index, &poly, options)),
+                        },
+                    };
+                    peak_monos = peak_monos.max(poly.number_of_monos());
+                    if collect_stats || limited {
+                        poly_stats = poly.stats();
+                    }
+                    if collect_stats {
+                        run_stats.push(StepStats {
+                            step,
+                            var: spec.var_name(*var),
+                            time: now.elapsed(),
+                            poly: poly_stats.clone(),
+                        });
                     }
+                if let Some(limit) = options.limits.check(poly_stats.nb_monos, poly_stats.memory_bytes, start.elapsed()) {
+                    return Err(limit_exceeded(spec, step, *var, limit, poly_stats));
                 }
-                /*if debug {
-                    poly = poly.integrateDebugger(spec, *var, from, to);
-                    // let memory = virtual_memory().expect("Impossible d'obtenir les informations sur la mémoire");
-                    if let Err(e) = writeln!(file_history, "pour créer le polynome {:?}. L'espace mémoire occupé est de TODO ", poly_pp(spec, &poly)) {
-                        eprintln!("Probleme pour écrire sur le fichier {}", e);
+                if let Some(path) = &options.checkpoint {
+                    if interrupted() {
+                        return Err(interrupt(spec, step, &poly, options));
+                    }
+                    if last_checkpoint.elapsed() >= options.checkpoint_interval && step < spec.elements.len() {
+                        Checkpoint::save(path, spec, step, &poly)?;
+                        last_checkpoint = Instant::now();
                     }
-                    step += 1;
-                } else {*/
-                    poly = poly.integrate(spec, *var, from, to);
-                    println!("  {:?}", poly_pp(spec, &poly));
-                    step += 1;
-              //  }
+                }
             }
 
+        if options.stats && !options.quiet {
+            println!("{}", run_stats.to_table());
+            if let Some(blowup) = run_stats.blowup_step() {
+                println!("Largest polynomial after step {} (d{})", blowup.step, blowup.var);
+            }
+        }
+        if let Some(path) = &options.stats_csv {
+            run_stats.save_csv(path)?;
+        }
 
         match poly.as_constant() {
-            None => Err("Stuck integral".to_string()),
-            Some(res) => Ok(res),
+            None => Err("Stuck integral".to_string().into()),
+            Some(res) => Ok((res, peak_monos)),
         }
     }
 
+fn limit_exceeded(spec: &IntegralSpec, step: usize, var: usize, limit: Limit, stats: PolyStats) -> IntegrationError {
+    IntegrationError::LimitExceeded {
+        limit,
+        step,
+        var: spec.var_name(var),
+        stats,
+    }
+}
+
+/// Writes the final checkpoint after a Ctrl-C and builds the corresponding error.
+fn interrupt(spec: &IntegralSpec, steps_done: usize, poly: &Poly, options: &IntegrationOptions) -> IntegrationError {
+    let path = options.checkpoint.as_deref().unwrap_or_default();
+    let msg = match Checkpoint::save(path, spec, steps_done, poly) {
+        Ok(()) => format!("Interrupted after step {steps_done}, resume with --resume {path}"),
+        Err(e) => format!("Interrupted after step {steps_done}, and {e}"),
+    };
+    IntegrationError::Failed(msg)
+}
+
+/**
+    We integrate using an integral (or a weighted sum of integrals) from a text val, we give it as
+    a spec after using the parser's function previously. Returns the result line (see
+    `format_result`) and the time spent.
+**/
+
+pub fn integrate_job(line: &str, options: &IntegrationOptions, le: bool) -> (Result<String, IntegrationError>, Duration) {
+    let debut = Instant::now();
+    let res = parser::parse_sum(line)
+        .map_err(IntegrationError::Failed)
+        .and_then(|sum| integrate_sum(&sum, options).map(|res| format_result(&res, sum_nbvars(&sum), le)));
+    (res, debut.elapsed())
+}
+
+/// Integrates `lines` with a pool of `jobs` worker threads. The results are written to
+/// `out` in the order of `lines`, as soon as all the previous ones are done. The statistics
+/// of each line go to their own CSV file (see `job_stats_csv`).
+pub fn integrate_batch<W: Write>(
+    lines: &[String],
+    out: &mut W,
+    options: &IntegrationOptions,
+    le: bool,
+    jobs: usize,
+) -> io::Result<()> {
+    let options = IntegrationOptions {
+        quiet: true,
+        checkpoint: None,
+        debug: false,
+        ..options.clone()
+    };
+    let next_job = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1).min(lines.len()) {
+            let sender = sender.clone();
+            let (next_job, options) = (&next_job, &options);
+            scope.spawn(move || loop {
+                let index = next_job.fetch_add(1, Ordering::SeqCst);
+                if index >= lines.len() {
+                    break;
+                }
+                let options = IntegrationOptions {
+                    stats_csv: options.stats_csv.as_deref().map(|path| job_stats_csv(path, index)),
+                    ..options.clone()
+                };
+                let (res, time) = integrate_job(&lines[index], &options, le);
+                if sender.send((index, res, time)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        // results arrive in any order, they are written back in input order
+        let mut pending = BTreeMap::new();
+        let mut next_out = 0;
+        for (index, res, time) in receiver {
+            pending.insert(index, (res, time));
+            while let Some((res, time)) = pending.remove(&next_out) {
+                match res {
+                    Ok(res) => writeln!(out, "{res} {:?}", time)?,
+                    Err(e) => writeln!(out, "error: {}", e.to_string().replace('\n', " "))?,
+                }
+                out.flush()?;
+                next_out += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// The CSV file of the statistics of the line `index` of a batch: the index is added before
+/// the extension of `path`, e.g. `stats.3.csv`, so that the jobs do not overwrite each other.
+fn job_stats_csv(path: &str, index: usize) -> String {
+    let path = PathBuf::from(path);
+    let name = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => format!("{}.{index}.{}", stem.to_string_lossy(), ext.to_string_lossy()),
+        (Some(stem), None) => format!("{}.{index}", stem.to_string_lossy()),
+        _ => index.to_string(),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
 /**
 This function is used when we run the project with cargo run --bin create_integrale, it takes all the
 integrals from a file and integrate them using parse.
  **/
 pub fn integrate_spec_file(
-    file: String){
+    file: String,
+    options: &IntegrationOptions,
+    le: bool,
+    jobs: usize,
+) -> Result<(), String> {
         let fichier_integrales = OpenOptions::new()
             .read(true)
-            .open(file)
-            .unwrap();
+            .open(&file)
+            .map_err(|e| format!("Cannot open {file}: {e}"))?;
         let file_reader = BufReader::new(fichier_integrales);
 
+        let mut lines = Vec::new();
         for line in file_reader.lines() {
-            if let Ok(trimmed_line) = line {
-                let trimmed_line = trimmed_line.trim();
-                if !trimmed_line.is_empty() {
-                    match parser::parse(trimmed_line) {
-                        Ok(spec) => {
-                            integrate_file(&spec);
-                        }
-                        Err(e) => abort("Parse error", &e),
-                    }
-
-                }
+            let line = line.map_err(|e| format!("Cannot read {file}: {e}"))?;
+            let trimmed_line = line.trim();
+            if !trimmed_line.is_empty() && !trimmed_line.starts_with("//") {
+                lines.push(trimmed_line.to_string());
             }
         }
+
+        integrate_batch(&lines, &mut io::stdout(), options, le, jobs)
+            .map_err(|e| format!("I/O error: {e}"))
+    }
+
+/// Formats a result as printed in quiet mode: the number of linear extensions if `le`,
+/// the fraction `num/den` otherwise.
+pub fn format_result(res: &BigRational, nbvars: usize, le: bool) -> String {
+    if le {
+        (res * BigRational::from_integer(factorial(nbvars))).to_string()
+    } else {
+        format!("{}/{}", res.numer(), res.denom())
     }
+}
+
+/// Reads integrals (or weighted sums of integrals) from `input`, one per line, and writes one result per line to `out`,
+/// flushed as soon as each integral is done. Blank lines and `//` comments are skipped;
+/// a line which cannot be integrated gives an `error: ...` line, so that the output lines
+/// always match the input integrals.
+pub fn integrate_spec_stream<R: BufRead, W: Write>(
+    input: R,
+    out: &mut W,
+    options: &IntegrationOptions,
+    le: bool,
+) -> Result<(), String> {
+    let io_error = |e: io::Error| format!("I/O error: {e}");
+    for line in input.lines() {
+        let line = line.map_err(io_error)?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+        let res = parser::parse_sum(line).map_err(IntegrationError::Failed).and_then(|sum| {
+            integrate_sum(&sum, options).map(|res| format_result(&res, sum_nbvars(&sum), le))
+        });
+        match res {
+            Ok(res) => writeln!(out, "{res}").map_err(io_error)?,
+            Err(e) => writeln!(out, "error: {}", e.to_string().replace('\n', " ")).map_err(io_error)?,
+        }
+        out.flush().map_err(io_error)?;
+    }
+    Ok(())
+}
 
 #[cfg(test)]
 mod tests {
+    use std::env;
+    use std::fs;
+
     use num::{BigInt, BigRational};
 
     use crate::{integral::Bound, integral::IntegralSpec, integrator::poly_pp};
+    use crate::history::History;
+    use crate::limits::Limit;
+    use crate::parser::parse;
 
     use super::{
-        antideriv_coef, antideriv_mono, mk_one_mono, mono_pp, mono_subst_const, mono_subst_var,
-        Poly,
+        antideriv_coef, antideriv_mono, integrate_batch, job_stats_csv, integrate_spec, integrate_spec_stream, mk_one_mono, mono_pp, mono_subst_const,
+        mono_subst_var, IntegrationError, IntegrationOptions, Poly,
     };
 
     #[test]
@@ -556,4 +922,103 @@ mod tests {
         assert_eq!(poly_pp(&spec, &p10), "1/6720");
         // correct number of linear extensions: #le = 10! / 6720 = 540
     }
+
+    #[test]
+    fn test_limits() {
+        let spec = parse("Int_0^1 Int_0^x3 Int_x3^1 Int_x3^1 Int_0^x3 1 dx1 dx5 dx4 dx2 dx3").unwrap();
+        let mut options = IntegrationOptions {
+            quiet: true,
+            ..IntegrationOptions::default()
+        };
+        options.limits.max_monomials = Some(2);
+        match integrate_spec(&spec, &options) {
+            Err(IntegrationError::LimitExceeded { limit, step, var, stats }) => {
+                assert_eq!(limit, Limit::Monomials(2));
+                assert_eq!(step, 3);
+                assert_eq!(var, "x4");
+                assert_eq!(stats.nb_monos, 3);
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+        options.limits.max_monomials = Some(3);
+        assert_eq!(integrate_spec(&spec, &options).unwrap().to_string(), "1/30");
+    }
+
+    #[test]
+    fn test_stream() {
+        let input = "// two integrals\nInt_0^1 Int_0^x2 1 dx1 dx2\n\nInt_0^1 1 dx1 dx2\nInt_0^1 Int_x1^1 1 dx2 dx1\n";
+        let options = IntegrationOptions {
+            quiet: true,
+            ..IntegrationOptions::default()
+        };
+        let mut out = Vec::new();
+        integrate_spec_stream(input.as_bytes(), &mut out, &options, false).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "1/2");
+        assert!(lines[1].starts_with("error: "));
+        assert_eq!(lines[2], "1/2");
+
+        let mut out = Vec::new();
+        integrate_spec_stream("Int_0^1 Int_0^x2 1 dx1 dx2".as_bytes(), &mut out, &options, true).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "1\n");
+
+        // a sum of integrals: the two orders of x1 and x2
+        let mut out = Vec::new();
+        let input = "Int_0^1 Int_0^x2 1 dx1 dx2 + Int_0^1 Int_x1^1 1 dx2 dx1\n3 * Int_0^1 Int_0^x2 1 dx1 dx2\n";
+        integrate_spec_stream(input.as_bytes(), &mut out, &options, true).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "2\n3\n");
+    }
+
+    #[test]
+    fn test_batch_keeps_input_order() {
+        let lines: Vec<String> = vec![
+            "Int_0^1 Int_0^x3 Int_x3^1 Int_x3^1 Int_0^x3 1 dx1 dx5 dx4 dx2 dx3",
+            "Int_0^1 Int_0^x2 1 dx1 dx2",
+            "Int_0^1 1 dx1 dx2",
+            "1",
+            "Int_0^1 Int_0^x3 Int_0^x2 1 dx1 dx2 dx3",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        let history = env::temp_dir().join(format!("rustegrator-test-batch-{}.jsonl", std::process::id()));
+        let mut options = IntegrationOptions {
+            history: Some(history.clone()),
+            ..IntegrationOptions::default()
+        };
+        options.limits.max_monomials = Some(2);
+        let mut out = Vec::new();
+        integrate_batch(&lines, &mut out, &options, false, 3).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let results: Vec<&str> = out.lines().map(|l| l.split(' ').next().unwrap()).collect();
+        assert_eq!(results, vec!["error:", "1/2", "error:", "1/1", "1/6"]);
+        assert!(out.lines().next().unwrap().contains("Limit exceeded"));
+
+        // one record per parsed integral, in completion order
+        let mut records = History::new(&history).records().unwrap();
+        fs::remove_file(&history).unwrap();
+        records.sort_by_key(|r| r.nbvars);
+        let summary: Vec<(usize, Option<&str>, usize)> =
+            records.iter().map(|r| (r.nbvars, r.result.as_deref(), r.peak_monomials)).collect();
+        assert_eq!(summary, vec![(0, Some("1"), 1), (2, Some("1/2"), 1), (3, Some("1/6"), 1), (5, None, 3)]);
+        assert!(records[3].error.as_ref().unwrap().contains("Limit exceeded"));
+
+        // each job writes its own statistics
+        let csv = env::temp_dir().join(format!("rustegrator-test-batch-{}.csv", std::process::id()));
+        let options = IntegrationOptions {
+            stats_csv: Some(csv.to_string_lossy().into_owned()),
+            ..IntegrationOptions::default()
+        };
+        integrate_batch(&lines[1..], &mut Vec::new(), &options, false, 3).unwrap();
+        for index in [0, 3] {
+            let path = job_stats_csv(&csv.to_string_lossy(), index);
+            assert!(fs::read_to_string(&path).unwrap().lines().count() > 1, "{path}");
+            fs::remove_file(path).unwrap();
+        }
+        assert!(!csv.exists());
+        assert_eq!(job_stats_csv("out/stats.csv", 3), "out/stats.3.csv");
+        assert_eq!(job_stats_csv("stats", 0), "stats.0");
+    }
 }
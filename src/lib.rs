@@ -0,0 +1,64 @@
+//! Exact computation of iterated integrals of the form
+//! `Int_0^1 Int_0^x3 Int_x3^1 1 dx1 dx2 dx3`, whose value times `n!` is the number of
+//! linear extensions of the poset they describe, and tools to build such integrals from
+//! directed acyclic graphs (BIT decomposition).
+//!
+//! The integrator can be linked directly:
+//!
+//! ```
+//! use rustegrator::integrator::{integrate_spec, IntegrationOptions};
+//! use rustegrator::parser::parse;
+//!
+//! let spec = parse("Int_0^1 Int_0^x2 1 dx1 dx2").unwrap();
+//! let options = IntegrationOptions { quiet: true, ..IntegrationOptions::default() };
+//! let res = integrate_spec(&spec, &options).unwrap();
+//! assert_eq!(res.to_string(), "1/2");
+//! ```
+//!
+//! and a DAG given as an adjacency matrix turned into an integral:
+//!
+//! ```
+//! use rustegrator::resolution_using_lists::list_BIT_resolution;
+//!
+//! // 0 -> 1 -> 2
+//! let integral = list_BIT_resolution(vec![vec![0, 1, 0], vec![0, 0, 1], vec![0, 0, 0]]).unwrap();
+//! assert!(rustegrator::parser::parse(&integral).is_ok());
+//! ```
+//!
+//! The `rustegrator` and `create_integrale` binaries are thin front-ends over this crate.
+
+// Integration
+pub mod integral;
+pub mod integrator;
+pub mod maths;
+pub mod parser;
+pub mod version;
+
+// Running integrations: options, resources, state and results
+pub mod cache;
+pub mod checkpoint;
+pub mod debugger;
+pub mod history;
+pub mod limits;
+pub mod repl;
+pub mod settings;
+pub mod stats;
+
+// DAGs and their BIT decomposition
+pub mod bench;
+pub mod bitmatrix;
+pub mod dot;
+pub mod families;
+pub mod generator_matrix;
+pub mod graph_io;
+pub mod matrix;
+pub mod poset;
+pub mod reachability;
+pub mod resolution_using_hashmap;
+pub mod resolution_using_lists;
+pub mod splitting;
+pub mod transitive_reduction;
+
+pub use integral::{Bound, IntegralSpec, WeightedIntegral};
+pub use integrator::{integrate_spec, integrate_sum, IntegrationError, IntegrationOptions, Poly};
+pub use parser::{parse, parse_sum};
@@ -0,0 +1,104 @@
+// This module describes the resource limits of an integration (number of monomials,
+// memory, time) so that a runaway integral stops cleanly instead of eating all the RAM.
+
+use std::fmt;
+use std::time::Duration;
+
+/// A limit which has been exceeded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Monomials(usize),
+    Memory(usize),
+    Timeout(Duration),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Monomials(max) => write!(f, "more than {max} monomials"),
+            Limit::Memory(max) => write!(f, "more than {max} bytes of memory"),
+            Limit::Timeout(max) => write!(f, "timeout of {:?}", max),
+        }
+    }
+}
+
+/// Resource limits of an integration, `None` meaning unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    pub max_monomials: Option<usize>,
+    pub max_memory: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+impl Limits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_monomials.is_none() && self.max_memory.is_none() && self.timeout.is_none()
+    }
+
+    /// Returns the first limit exceeded by a polynomial of `nb_monos` monomials using
+    /// `memory_bytes` bytes, after `elapsed` time of integration.
+    pub fn check(&self, nb_monos: usize, memory_bytes: usize, elapsed: Duration) -> Option<Limit> {
+        match (self.max_monomials, self.max_memory, self.timeout) {
+            (Some(max), _, _) if nb_monos > max => Some(Limit::Monomials(max)),
+            (_, Some(max), _) if memory_bytes > max => Some(Limit::Memory(max)),
+            (_, _, Some(max)) if elapsed > max => Some(Limit::Timeout(max)),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a memory size such as `512M`, `4G` or `1000000` (bytes).
+pub fn parse_size(input: &str) -> Result<usize, String> {
+    let input = input.trim();
+    let (digits, unit) = match input.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => input.split_at(i),
+        None => (input, ""),
+    };
+    let factor: usize = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("Unknown size unit: {unit}")),
+    };
+    match digits.parse::<usize>() {
+        Ok(n) => n.checked_mul(factor).ok_or_else(|| format!("Size too big: {input}")),
+        Err(_) => Err(format!("Bad size: {input}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_size, Limit, Limits};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1000"), Ok(1000));
+        assert_eq!(parse_size("4K"), Ok(4096));
+        assert_eq!(parse_size("512MiB"), Ok(512 << 20));
+        assert_eq!(parse_size("2g"), Ok(2 << 30));
+        assert!(parse_size("12X").is_err());
+        assert!(parse_size("G").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let limits = Limits {
+            max_monomials: Some(10),
+            max_memory: Some(1000),
+            timeout: Some(Duration::from_secs(1)),
+        };
+        assert_eq!(limits.check(10, 1000, Duration::from_millis(10)), None);
+        assert_eq!(limits.check(11, 0, Duration::ZERO), Some(Limit::Monomials(10)));
+        assert_eq!(limits.check(1, 1001, Duration::ZERO), Some(Limit::Memory(1000)));
+        assert_eq!(
+            limits.check(1, 1, Duration::from_secs(2)),
+            Some(Limit::Timeout(Duration::from_secs(1)))
+        );
+        assert!(Limits::default().is_unlimited());
+        assert_eq!(Limits::default().check(usize::MAX, usize::MAX, Duration::MAX), None);
+    }
+}
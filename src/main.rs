@@ -1,49 +1,611 @@
-mod integral;
-mod integrator;
-mod maths;
-mod parser;
-mod version;
-
-mod generator_matrix;
-mod transitive_reduction;
-
 use clap::Parser;
-use num::BigInt;
+use num::{BigInt, BigRational};
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 
 
-use integrator::integrate_spec;
-use version::{VERSION_MAJOR, VERSION_MINOR};
-use crate::integrator::{integrate_spec_file};
+use rustegrator::integrator::{integrate_sum, resume_spec};
+use rustegrator::parser;
+use rustegrator::version::{VERSION_MAJOR, VERSION_MINOR};
+use rustegrator::bench::{run_bench, BenchOptions};
+use rustegrator::cache::{canonical_hash, default_cache_dir, Cache};
+use rustegrator::generator_matrix::{random_seed, rng_from_seed, GraphKind};
+use rustegrator::history::{compare, format_timestamp, Filter, History, DEFAULT_HISTORY_FILE};
+use rustegrator::integrator::{format_result, integrate_spec_file, integrate_spec_stream, IntegrationOptions};
+use rustegrator::limits::{parse_size, Limits};
+use rustegrator::poset::Poset;
+use rustegrator::dot::{is_dot, parse_dot, steps_to_dot};
+use rustegrator::graph_io::{format_graphs, format_graphs_seeded, guess_format, load_graphs, read_graphs, read_source, EdgeList, GraphFormat};
+use rustegrator::repl::Repl;
+use rustegrator::resolution_using_hashmap::Graph;
+use rustegrator::resolution_using_lists::{list_BIT_resolution, resolution_steps, BitError};
+use rustegrator::splitting::{split_resolution, MAX_INTEGRALS};
+use rustegrator::integral::{format_sum, WeightedIntegral};
+use rustegrator::settings::{OutputFormat, Settings};
+use rustegrator::transitive_reduction::transitive_reduction;
 
 #[derive(clap::Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // options of the integration when no subcommand is given
+    #[command(flatten)]
+    integrate: IntegrateArgs,
+
     /// Run in quiet mode (only show end result)
-    #[arg(short, long)]
+    #[arg(short, long, global = true, overrides_with = "no_quiet")]
     quiet: bool,
 
-    //Debug mode
-    #[arg(short, long)]
-    debug: bool,
+    /// Show the progress, even if the configuration sets quiet
+    #[arg(long, global = true, overrides_with = "quiet")]
+    no_quiet: bool,
+
     /// Compute linear extension count instead of integration result
-    #[arg(short, long)]
+    #[arg(short, long, global = true, overrides_with = "fraction")]
     le: bool,
 
-    /// Take a file in
-    #[arg(long)]
-    file: Option<String>,
+    /// Print the integration result, even if the configuration sets output = "le"
+    #[arg(long, global = true, overrides_with = "le")]
+    fraction: bool,
+
     /// Show formula at each integration step
-    #[arg(short, long)]
+    #[arg(short, long, global = true, overrides_with = "no_formula")]
     formula: bool,
 
+    /// Do not show the formulas, even if the configuration sets formula
+    #[arg(long, global = true, overrides_with = "formula")]
+    no_formula: bool,
+
     /// Show statistics at each integration step
-    #[arg(short, long)]
+    #[arg(short, long, global = true, overrides_with = "no_stats")]
     stats: bool,
 
+    /// Do not show the statistics, even if the configuration sets stats
+    #[arg(long, global = true, overrides_with = "stats")]
+    no_stats: bool,
+
+    /// Do not look up nor store results in the cache
+    #[arg(long, global = true, overrides_with = "cache")]
+    no_cache: bool,
+
+    /// Use the cache, even if the configuration sets cache = false
+    #[arg(long, global = true, overrides_with = "no_cache")]
+    cache: bool,
+
+    /// Directory of the result cache
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+
+    /// Run log where a JSON record is appended after each integration [default: historique.jsonl]
+    #[arg(long, global = true)]
+    history_file: Option<PathBuf>,
+
+    /// Do not record the integrations in the run log
+    #[arg(long, global = true, overrides_with = "history")]
+    no_history: bool,
+
+    /// Record the integrations, even if the configuration sets history = false
+    #[arg(long, global = true, overrides_with = "no_history")]
+    history: bool,
+
+    /// Read the defaults from this configuration file instead of rustegrator.toml
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+}
+
+// Options of an integration, shared by the top level command and `integrate`/`count`.
+#[derive(clap::Args, Debug, Clone, Default)]
+struct IntegrateArgs {
+    /// Run the integration in the interactive step debugger
+    #[arg(short, long)]
+    debug: bool,
+
+    /// Take a file in (integrals are read from stdin with `-` or when no integral is given)
+    #[arg(long)]
+    file: Option<String>,
+
+    /// Write the statistics of each integration step to a CSV file (with --file, one file per
+    /// integral of the file: stats.0.csv, stats.1.csv, ...)
+    #[arg(long)]
+    stats_csv: Option<String>,
+
     #[arg(long)]
     /// The integral to compute
     integral: Option<String>,
+
+    /// Periodically save the integration state to this file (also done on Ctrl-C)
+    #[arg(long)]
+    checkpoint: Option<String>,
+
+    /// Minimum number of seconds between two checkpoints [default: 60]
+    #[arg(long)]
+    checkpoint_interval: Option<u64>,
+
+    /// Resume the integration saved in this checkpoint file
+    #[arg(long)]
+    resume: Option<String>,
+
+    /// Stop when the polynomial has more than this number of monomials
+    #[arg(long)]
+    max_monomials: Option<usize>,
+
+    /// Stop when the polynomial uses more than this memory (e.g. 512M, 4G)
+    #[arg(long, value_parser = parse_size)]
+    max_memory: Option<usize>,
+
+    /// Stop after this number of seconds
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Number of integrals of a file integrated in parallel [default: number of CPUs]
+    #[arg(short, long)]
+    jobs: Option<usize>,
+}
+
+impl Args {
+    /// Fills the options not given on the command line with the configured defaults: a flag
+    /// or its negation (`--no-stats`, `--fraction`...) overrides the settings.
+    fn apply_settings(&mut self, settings: Settings) -> Result<(), String> {
+        self.quiet = !self.no_quiet && (self.quiet || settings.quiet);
+        self.le = !self.fraction && (self.le || settings.output == OutputFormat::Le);
+        self.formula = !self.no_formula && (self.formula || settings.formula);
+        self.stats = !self.no_stats && (self.stats || settings.stats);
+        self.no_cache = !self.cache && (self.no_cache || settings.cache == Some(false));
+        self.no_history = !self.history && (self.no_history || settings.history == Some(false));
+        self.cache_dir = self.cache_dir.take().or(settings.cache_dir.clone());
+        self.history_file = self.history_file.take().or(settings.history_file.clone());
+        match &mut self.command {
+            Some(Command::Integrate(cmd)) | Some(Command::Count(cmd)) => cmd.args.apply_settings(&settings),
+            _ => self.integrate.apply_settings(&settings),
+        }
+    }
+
+    /// Builds the options of an integration run with `args`.
+    fn integration_options(&self, args: &IntegrateArgs) -> IntegrationOptions {
+        IntegrationOptions {
+            quiet: self.quiet,
+            formula: self.formula,
+            stats: self.stats,
+            stats_csv: args.stats_csv.clone(),
+            checkpoint: args.checkpoint.clone().or(args.resume.clone()),
+            checkpoint_interval: Duration::from_secs(args.checkpoint_interval.unwrap_or(60)),
+            limits: Limits {
+                max_monomials: args.max_monomials,
+                max_memory: args.max_memory,
+                timeout: args.timeout.map(Duration::from_secs),
+            },
+            debug: args.debug,
+            cache: if self.no_cache { None } else { Some(self.cache_dir()) },
+            history: if self.no_history { None } else { Some(self.history_file()) },
+        }
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.cache_dir.clone().unwrap_or_else(default_cache_dir)
+    }
+
+    fn history_file(&self) -> PathBuf {
+        self.history_file.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_HISTORY_FILE))
+    }
+}
+
+impl IntegrateArgs {
+    fn apply_settings(&mut self, settings: &Settings) -> Result<(), String> {
+        self.stats_csv = self.stats_csv.take().or(settings.stats_csv.clone());
+        self.max_monomials = self.max_monomials.or(settings.max_monomials);
+        if self.max_memory.is_none() {
+            self.max_memory = settings.max_memory.as_deref().map(parse_size).transpose()?;
+        }
+        self.timeout = self.timeout.or(settings.timeout);
+        self.jobs = self.jobs.or(settings.jobs);
+        self.checkpoint_interval = self.checkpoint_interval.or(settings.checkpoint_interval);
+        Ok(())
+    }
+
+    /// Whether the integrals are streamed from stdin (stdout then only carries the results).
+    fn streaming(&self) -> bool {
+        self.resume.is_none() && self.integral.is_none() && self.file.as_deref().is_none_or(|f| f == "-")
+    }
+}
+
+fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Compute integrals, given as argument, in a file (--file) or on stdin
+    Integrate(IntegrateCommand),
+    /// Count the linear extensions (#le) of the posets described by integrals
+    Count(IntegrateCommand),
+    /// Generate random DAGs as adjacency matrices
+    Generate(GenerateArgs),
+    /// Compute the transitive reduction of DAGs given as adjacency matrices
+    Reduce(ReduceArgs),
+    /// Decompose DAGs given as adjacency matrices into integrals (BIT resolution)
+    Decompose(DecomposeArgs),
+    /// Time the two BIT resolutions on random DAGs of increasing size
+    Bench(BenchArgs),
+    /// Count the linear extensions of a poset given by constraints such as "a < b, b < c"
+    Poset(PosetArgs),
+    /// Interactive mode: type integrals, bind their results to names and compute with them
+    Repl,
+    /// Inspect or clean the result cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Show past runs from the run log, or compare them to spot performance regressions
+    History(HistoryArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct IntegrateCommand {
+    /// The integral to compute (same as --integral)
+    #[arg(value_name = "INTEGRAL", conflicts_with_all = ["integral", "file", "resume"])]
+    source: Option<String>,
+
+    #[command(flatten)]
+    args: IntegrateArgs,
+}
+
+impl IntegrateCommand {
+    fn into_args(self) -> IntegrateArgs {
+        IntegrateArgs {
+            integral: self.source.or(self.args.integral),
+            ..self.args
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct GenerateArgs {
+    /// Number of nodes of each DAG (the parameter of a family)
+    #[arg(short, long, default_value_t = 10)]
+    nodes: usize,
+    /// Number of DAGs to generate
+    #[arg(short, long, default_value_t = 1)]
+    count: usize,
+    /// Shape of the DAGs: chain, random (random tree), erdos-renyi[:P], layered[:W1,W2,...],
+    /// in-forest[:TREES], width:W, height:H, dimension[:D], graph-order[:P], or a family whose parameter is the size: antichain,
+    /// fence, crown, grid[:AxB], boolean, binary-tree, young[:R1,R2,...]
+    #[arg(short, long, default_value = "chain")]
+    kind: GraphKind,
+    /// Write the DAGs to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Output format: matrix, csv, edges, json or dot
+    #[arg(long, default_value = "matrix")]
+    format: GraphFormat,
+    /// Seed of the random generator (written in the output), drawn at random by default
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReduceArgs {
+    /// File of DAGs (`-` for stdin)
+    #[arg(default_value = "-")]
+    file: String,
+    /// Input format: matrix, csv, edges, json or dot (guessed by default)
+    #[arg(long)]
+    from: Option<GraphFormat>,
+    /// Output format
+    #[arg(long, default_value = "matrix")]
+    to: GraphFormat,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Resolution {
+    /// Adjacency matrix
+    Lists,
+    /// Adjacency and predecessor hash maps
+    Hashmap,
+}
+
+#[derive(clap::Args, Debug)]
+struct DecomposeArgs {
+    /// File of DAGs (`-` for stdin); the nodes of a DOT digraph give the variable names
+    #[arg(default_value = "-")]
+    file: String,
+    /// Input format: matrix, csv, edges, json or dot (guessed by default)
+    #[arg(long)]
+    from: Option<GraphFormat>,
+    /// Implementation of the BIT resolution
+    #[arg(short, long, value_enum, default_value_t = Resolution::Lists)]
+    method: Resolution,
+    /// Write the graph after each step of the resolution as Graphviz DOT graphs
+    #[arg(long)]
+    steps: bool,
+    /// Split the DAGs which are not forests into a weighted sum of integrals
+    /// (`2 * Int_... + Int_...`, read by `count`), with the lists resolution
+    #[arg(long, conflicts_with_all = ["steps", "method"])]
+    split: bool,
+    /// Maximal number of integrals of a split
+    #[arg(long, default_value_t = MAX_INTEGRALS)]
+    max_integrals: usize,
+}
+
+#[derive(clap::Args, Debug)]
+struct PosetArgs {
+    /// The constraints: chains (a < b < c, d > b) or isolated elements, separated by commas
+    #[arg(conflicts_with = "file")]
+    constraints: Option<String>,
+    /// Read the constraints, or a DOT digraph, from this file (`-` for stdin)
+    #[arg(long)]
+    file: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct BenchArgs {
+    /// Smallest number of nodes
+    #[arg(long, default_value_t = 5)]
+    from: usize,
+    /// Largest number of nodes
+    #[arg(long, default_value_t = 150)]
+    to: usize,
+    /// Shape of the DAGs: chain, random (random tree), erdos-renyi[:P], layered[:W1,W2,...],
+    /// in-forest[:TREES], width:W, height:H, dimension[:D], graph-order[:P], or a family whose parameter is the size: antichain,
+    /// fence, crown, grid[:AxB], boolean, binary-tree, young[:R1,R2,...]
+    #[arg(short, long, default_value = "chain")]
+    kind: GraphKind,
+    /// Directory of the result files
+    #[arg(short, long, default_value = ".")]
+    output_dir: PathBuf,
+    /// Seed of the random DAGs (written in the result files), drawn at random by default
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct HistoryArgs {
+    /// Only the runs of this integral (under any variable names)
+    #[arg(long)]
+    integral: Option<String>,
+    /// Only the runs of the integrals whose canonical hash starts with this prefix
+    #[arg(long, conflicts_with = "integral")]
+    hash: Option<String>,
+    /// Only the runs of integrals with this number of variables
+    #[arg(long)]
+    vars: Option<usize>,
+    /// Only the runs of the last DAYS days
+    #[arg(long, value_name = "DAYS")]
+    since: Option<u64>,
+    /// Only the failed runs
+    #[arg(long)]
+    failed: bool,
+    /// Only show the last N runs
+    #[arg(long, value_name = "N")]
+    last: Option<usize>,
+    /// Compare the latest run of each integral with the fastest previous one
+    #[arg(long)]
+    compare: bool,
+    /// Slowdown ratio above which the latest run is reported as a regression
+    #[arg(long, default_value_t = 1.2, requires = "compare")]
+    threshold: f64,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum CacheAction {
+    /// List the cached results
+    List,
+    /// Remove cached results (and unreadable entries)
+    Prune {
+        /// Only remove the results older than this number of days
+        #[arg(long)]
+        older_than: Option<u64>,
+        /// Remove all the results
+        #[arg(long, conflicts_with = "older_than")]
+        all: bool,
+    },
+}
+
+fn cache_command(cache: &Cache, action: &CacheAction) -> Result<(), String> {
+    match action {
+        CacheAction::List => {
+            for (path, entry) in cache.entries()? {
+                match entry {
+                    Ok(entry) => println!("{}  {:>4} vars  {:>12?}  {}", entry.result, entry.nbvars, entry.time, entry.canonical),
+                    Err(e) => println!("unreadable entry {}: {e}", path.display()),
+                }
+            }
+            Ok(())
+        }
+        CacheAction::Prune { older_than, all } => {
+            if older_than.is_none() && !all {
+                return Err("Use --older-than <DAYS> or --all".to_string());
+            }
+            let removed = cache.prune(older_than.map(|days| Duration::from_secs(days * 24 * 3600)))?;
+            println!("{removed} entries removed");
+            Ok(())
+        }
+    }
+}
+
+fn history_command(history: &History, args: &HistoryArgs) -> Result<(), String> {
+    let hash = match &args.integral {
+        Some(integral) => Some(canonical_hash(&parser::parse(integral)?)),
+        None => args.hash.clone(),
+    };
+    let filter = Filter {
+        hash,
+        nbvars: args.vars,
+        since: args.since.map(|days| rustegrator::history::now_timestamp().saturating_sub(days * 24 * 3600)),
+        failed_only: args.failed,
+    };
+    let mut records: Vec<_> = history.records()?.into_iter().filter(|r| filter.matches(r)).collect();
+
+    if args.compare {
+        let comparisons = compare(&records);
+        let mut regressions = 0;
+        for c in &comparisons {
+            let regression = c.is_regression(args.threshold);
+            regressions += regression as usize;
+            println!(
+                "{}  {:>4} vars  {:>3} runs  best {:>12.3?}  latest {:>12.3?}  x{:<6.2}  peak {} -> {}{}{}",
+                c.hash,
+                c.nbvars,
+                c.runs,
+                c.best,
+                c.latest,
+                c.ratio,
+                c.peak_monomials.0,
+                c.peak_monomials.1,
+                if c.result_changed { "  RESULT CHANGED" } else { "" },
+                if regression { "  REGRESSION" } else { "" }
+            );
+        }
+        return match regressions {
+            0 => Ok(()),
+            n => Err(format!("{n} regression(s) out of {} integrals", comparisons.len())),
+        };
+    }
+
+    if let Some(last) = args.last {
+        records.drain(..records.len().saturating_sub(last));
+    }
+    for r in &records {
+        let outcome = match (&r.result, &r.error) {
+            (Some(result), _) => result.clone(),
+            (None, error) => format!("error: {}", error.as_deref().unwrap_or("unknown")),
+        };
+        println!(
+            "{}  {}  {:>4} vars  {:>12.3?}  peak {:>8}  {}{}",
+            format_timestamp(r.timestamp),
+            r.hash,
+            r.nbvars,
+            r.time(),
+            r.peak_monomials,
+            outcome,
+            if r.cached { " (cached)" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+fn generate_command(args: &GenerateArgs) -> Result<(), String> {
+    if args.nodes == 0 {
+        return Err("A DAG needs at least one node".to_string());
+    }
+    let seed = args.seed.unwrap_or_else(random_seed);
+    let mut rng = rng_from_seed(seed);
+    let graphs = (0..args.count)
+        .map(|_| args.kind.generate(args.nodes, &mut rng).map(|matrice| EdgeList::from_matrice(&matrice)))
+        .collect::<Result<Vec<EdgeList>, String>>()?;
+    let content = format_graphs_seeded(&graphs, args.format, seed);
+    match &args.output {
+        Some(path) => fs::write(path, content).map_err(|e| format!("Cannot write {}: {e}", path.display())),
+        None => {
+            print!("{content}");
+            Ok(())
+        }
+    }
+}
+
+fn reduce_command(args: &ReduceArgs) -> Result<(), String> {
+    // the graphs read are checked to be acyclic
+    let reduced: Vec<EdgeList> = read_graphs(&args.file, args.from)?
+        .into_iter()
+        .map(|graph| EdgeList::from_matrice(&transitive_reduction(&graph.to_matrice()).unwrap()))
+        .collect();
+    print!("{}", format_graphs(&reduced, args.to));
+    Ok(())
+}
+
+fn decompose_command(args: &DecomposeArgs) -> Result<(), String> {
+    let source = read_source(&args.file)?;
+    let format = guess_format(&args.file, &source, args.from);
+    if format == GraphFormat::Dot && !args.steps {
+        // the nodes keep their names, so the components are decomposed as a poset
+        let poset = parse_dot(&source)?;
+        let integrals = if args.split {
+            poset.integrals_with(|matrice| split_resolution(matrice, args.max_integrals))?
+        } else {
+            poset.integrals_with(|matrice| {
+                let integral = bit_resolution(matrice.to_vec(), args.method).map_err(|e| e.to_string())?;
+                Ok(vec![WeightedIntegral::new(BigInt::from(1), parser::parse(&integral)?)])
+            })?
+        };
+        println!("{}", format_sum(&integrals));
+        return Ok(());
+    }
+    for graph in load_graphs(&source, &args.file, Some(format))? {
+        let matrice = graph.to_matrice();
+        if args.split {
+            println!("{}", format_sum(&split_resolution(&matrice, args.max_integrals)?));
+            continue;
+        }
+        if args.steps {
+            let (integral, steps) = match args.method {
+                Resolution::Lists => resolution_steps(matrice.clone()),
+                Resolution::Hashmap => Graph::from_matrice(matrice.clone()).resolution_steps(),
+            };
+            print!("{}", steps_to_dot(&matrice, &steps, &integral));
+            continue;
+        }
+        println!("{}", bit_resolution(matrice, args.method).map_err(|e| e.to_string())?);
+    }
+    Ok(())
+}
+
+/// Decomposes a DAG with the BIT resolution implemented by `method`.
+fn bit_resolution(matrice: Vec<Vec<i32>>, method: Resolution) -> Result<String, BitError> {
+    match method {
+        Resolution::Lists => list_BIT_resolution(matrice),
+        Resolution::Hashmap => Graph::from_matrice(matrice).resolution_adjacence(),
+    }
+}
+
+fn poset_command(config: &Args, args: &PosetArgs) -> Result<(), String> {
+    let source = match &args.constraints {
+        Some(constraints) => constraints.clone(),
+        None => read_source(args.file.as_deref().unwrap_or("-"))?,
+    };
+    let poset = if is_dot(&source) { parse_dot(&source)? } else { Poset::parse(&source)? };
+    let options = IntegrationOptions {
+        quiet: true,
+        ..config.integration_options(&config.integrate)
+    };
+    let le = poset.linear_extensions(&options)?;
+    if config.quiet {
+        println!("{} {}", le.probability, le.count);
+        return Ok(());
+    }
+    // the elements whose name could not be used as a variable name
+    let renamed: Vec<String> = poset
+        .names()
+        .iter()
+        .zip(poset.variable_names())
+        .filter(|(name, var)| *name != var)
+        .map(|(name, var)| format!("{var} = {name}"))
+        .collect();
+    if !renamed.is_empty() {
+        println!("Variables: {}", renamed.join(", "));
+    }
+    println!("Integral: {}", le.integral);
+    println!("Probability: {}", le.probability);
+    println!("#le = {}", le.count);
+    Ok(())
+}
+
+fn bench_command(config: &Args, args: &BenchArgs) -> Result<(), String> {
+    let options = BenchOptions {
+        from: args.from,
+        to: args.to,
+        kind: args.kind.clone(),
+        output_dir: args.output_dir.clone(),
+        quiet: config.quiet,
+        seed: args.seed,
+    };
+    match run_bench(&options)? {
+        0 => Ok(()),
+        n => Err(format!("The two resolutions differ on {n} sizes")),
+    }
 }
 
 fn abort(header: &str, msg: &str) {
@@ -51,52 +613,107 @@ fn abort(header: &str, msg: &str) {
     eprintln!("<Abort>");
     process::exit(1)
 }
-fn main() {
-    let config = Args::parse();
+fn exit_on_error(header: &str, res: Result<(), String>) {
+    if let Err(e) = res {
+        abort(header, &e);
+    }
+}
 
-    if !config.quiet {
-        println!("Rust(int)egrator v{}.{}", VERSION_MAJOR, VERSION_MINOR);
-        println!("---------------------");
+fn print_result(config: &Args, res: BigRational, nbvars: usize, le: bool) {
+    let nres = format_result(&res, nbvars, le);
+    if le && !config.quiet {
+        println!("#le = {}", nres);
+    } else {
+        println!("{}", nres);
     }
+}
 
-    // Parse the integral input to get a specification
-    if let Some(integral) = config.integral {
-        match parser::parse(&integral) {
+/// Runs an integration: resume a checkpoint, one integral, the integrals of a file,
+/// or the integrals read from stdin.
+fn integrate_command(config: &Args, args: IntegrateArgs, le: bool) {
+    let options = config.integration_options(&args);
+    if let Some(checkpoint) = &args.resume {
+        match resume_spec(checkpoint, &options) {
+            Err(e) => abort("Integration error", &e.to_string()),
+            Ok((res, nbvars)) => print_result(config, res, nbvars, le),
+        }
+    }
+    // Parse the integral input (or a weighted sum of integrals) to get a specification
+    else if let Some(integral) = &args.integral {
+        match parser::parse_sum(integral) {
             Err(e) => abort("Parse error", &e),
-            Ok(spec) => {
-                    let nbvars = spec.var_map.len() + 1;
-                    match integrate_spec(
-                        &spec,
-                        config.quiet,
-                        config.formula,
-                        config.stats) {
-                        Err(e) => abort("Integration error", &e),
-                        Ok(res) => {
-                            if config.le {
-                                let nres = res * maths::factorial(nbvars);
-                                if !config.quiet {
-                                    println!("#le = {}", nres);
-                                } else {
-                                    println!("{}", nres);
-                                }
-                            } else {
-                                let num = res.numer();
-                                let den = BigInt::from(res.denom().clone());
-                                println!("{}/{}", num, den);
-                            }
-                        }
+            Ok(sum) => {
+                    let nbvars = sum.first().map_or(0, |integral| integral.spec.var_map.len());
+                    match integrate_sum(&sum, &options) {
+                        Err(e) => abort("Integration error", &e.to_string()),
+                        Ok(res) => print_result(config, res, nbvars, le),
                     }
                 }
             }
         }
 
-    else if let Some(file) = config.file {  // Access the inner `String` if it exists
-        // Function to handle file processing should be called here
-        integrate_spec_file(file);  // Pass the reference to the file string
-    }else{ abort("MISSING ARG","You should run a file, select \
-    \n --bin create_integrale in order to create integrals from integrales.txt
-     \n --bin --<OPTIONS> rustegrator --<OPTIONAL_FILE> --<OPTIONAL_INTEGRAL>") }
+    else if let Some(file) = args.file.filter(|f| f != "-") {  // Access the inner `String` if it exists
+        let jobs = args.jobs.unwrap_or_else(default_jobs);
+        if let Err(e) = integrate_spec_file(file, &options, le, jobs) {
+            abort("File error", &e);
+        }
+    } else {
+        // No integral given (or --file -): stream the integrals from stdin
+        let stdin = io::stdin();
+        if stdin.is_terminal() {
+            eprintln!("Reading integrals from stdin, one per line (Ctrl-D to end)");
+        }
+        let options = IntegrationOptions { quiet: true, ..options };
+        if let Err(e) = integrate_spec_stream(stdin.lock(), &mut io::stdout(), &options, le) {
+            abort("Stream error", &e);
+        }
+    }
+}
+
+fn main() {
+    let mut config = Args::parse();
+    match Settings::load(config.config.as_deref()) {
+        Err(e) => abort("Configuration error", &e),
+        Ok(settings) => {
+            if let Err(e) = config.apply_settings(settings) {
+                abort("Configuration error", &e);
+            }
+        }
+    }
 
+    // The header is only shown for the integrations which do not write their results to stdout
+    let interactive = match &config.command {
+        None => !config.integrate.streaming(),
+        Some(Command::Integrate(cmd)) | Some(Command::Count(cmd)) => cmd.source.is_some() || !cmd.args.streaming(),
+        Some(Command::Repl) => true,
+        Some(_) => false,
+    };
+    if !config.quiet && interactive {
+        println!("Rust(int)egrator v{}.{}", VERSION_MAJOR, VERSION_MINOR);
+        println!("---------------------");
+    }
 
+    match config.command.take() {
+        None => {
+            let args = config.integrate.clone();
+            let le = config.le;
+            integrate_command(&config, args, le);
+        }
+        Some(Command::Integrate(cmd)) => {
+            let le = config.le;
+            integrate_command(&config, cmd.into_args(), le);
+        }
+        Some(Command::Count(cmd)) => integrate_command(&config, cmd.into_args(), true),
+        Some(Command::Generate(args)) => exit_on_error("Generate error", generate_command(&args)),
+        Some(Command::Reduce(args)) => exit_on_error("Reduce error", reduce_command(&args)),
+        Some(Command::Decompose(args)) => exit_on_error("Decompose error", decompose_command(&args)),
+        Some(Command::Bench(args)) => exit_on_error("Bench error", bench_command(&config, &args)),
+        Some(Command::Poset(args)) => exit_on_error("Poset error", poset_command(&config, &args)),
+        Some(Command::Repl) => {
+            let mut repl = Repl::new(config.integration_options(&config.integrate), config.le);
+            exit_on_error("REPL error", repl.run(&mut io::stdin().lock(), &mut io::stdout()));
+        }
+        Some(Command::Cache { action }) => exit_on_error("Cache error", cache_command(&Cache::new(config.cache_dir()), &action)),
+        Some(Command::History(args)) => exit_on_error("History", history_command(&History::new(config.history_file()), &args)),
+    }
 }
-
@@ -0,0 +1,108 @@
+// This module gathers the helpers on adjacency matrices (`Vec<Vec<i32>>`, 1 for an edge)
+// shared by the generators, the transitive reductions and the BIT resolutions: reading and
+// writing them (see `graph_io` for the other formats), and converting them to adjacency
+// and predecessor lists.
+
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+use crate::graph_io::read_graphs;
+
+/// Displays a matrix in a readable format.
+/// Each row of the matrix is displayed in brackets, separated by commas.
+pub fn afficher_matrice(matrice: Vec<Vec<i32>>) {
+    let lignes_affichees: Vec<String> = matrice.iter()
+        .map(|ligne| {
+            format!("[{}]", ligne.iter()
+                .map(|&val| val.to_string())
+                .collect::<Vec<String>>()
+                .join(", "))
+        })
+        .collect();
+
+    println!("[{}]", lignes_affichees.join(", "));
+}
+
+
+/// Verifies if all elements in a matrix are zero, indicating resolution of all nodes.
+/// Returns true if all nodes are resolved, false otherwise.
+pub fn verify_all_zero(matrice: Vec<Vec<i32>>) -> bool {
+    for ligne in matrice {
+        for valeur in ligne {
+            if valeur == 1 {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+
+/// Reads a matrix from a specified file path, in any format of `graph_io`.
+/// Returns a vector of vectors of integers representing the matrix, or an error locating the
+/// faulty value.
+pub fn lire_matrice_à_partir_du_fichier(chemin: &str) -> Result<Vec<Vec<i32>>, String> {
+    match read_graphs(chemin, None)?.first() {
+        Some(graph) => Ok(graph.to_matrice()),
+        None => Err(format!("{chemin}: no graph")),
+    }
+}
+
+/// Formats a matrix with one row per line, the values separated by spaces
+/// (the `matrix` format of `graph_io`).
+///
+/// # Arguments
+///
+/// * `matrice` - The adjacency matrix to format.
+///
+/// # Returns
+///
+/// The rows of the matrix, each one ended by a newline.
+pub fn format_matrice(matrice: &[Vec<i32>]) -> String {
+    let mut res = String::new();
+    for ligne in matrice {
+        let valeurs: Vec<String> = ligne.iter().map(|v| v.to_string()).collect();
+        res.push_str(&valeurs.join(" "));
+        res.push('\n');
+    }
+    res
+}
+
+/// Constructs an adjacency list from a matrix.
+pub fn construire_adj(matrice: Vec<Vec<i32>>) -> HashMap<usize, Vec<usize>> {
+    let mut adj = HashMap::new();
+    for (i, ligne) in matrice.iter().enumerate() {
+        let mut successeurs = Vec::new();
+        for &valeur in ligne.iter() {
+            if valeur == 1 {
+                successeurs.push(1);
+            } else {
+                successeurs.push(0);
+            }
+        }
+        adj.insert(i, successeurs);
+    }
+    adj
+}
+
+/// Reads adjacency data from a file and returns a tuple containing the adjacency list and its size.
+pub fn lire_adjacence_a_partir_du_fichier(chemin: &str) -> Result<(HashMap<usize, Vec<usize>>, usize), String> {
+    let matrice = lire_matrice_à_partir_du_fichier(chemin)?;
+    let size = matrice.len();
+    Ok((construire_adj(matrice), size))
+}
+
+/// Checks if all indices from 0 to `taille-1` are present in a given string.
+pub fn verifier_indices(chaine: &str, taille: usize) -> bool {
+    let re = Regex::new(r"1 (\d+)").unwrap();
+    let mut indices = HashSet::new();
+    for cap in re.captures_iter(chaine) {
+        if let Some(matched) = cap.get(1) {
+            if let Ok(num) = matched.as_str().parse::<usize>() {
+                indices.insert(num);
+            }
+        }
+    }
+    (0..taille).all(|i| indices.contains(&i))
+}
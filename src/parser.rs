@@ -1,7 +1,9 @@
 use pest::Parser;
 use pest_derive::Parser;
 
-use crate::integral::{Bound, IntegralSpec};
+use num::BigInt;
+
+use crate::integral::{Bound, IntegralSpec, WeightedIntegral};
 
 #[derive(Parser)]
 #[grammar = "integral.pest"]
@@ -30,6 +32,8 @@ fn parse_bounds(mut bound_specs: pest::iterators::Pairs<Rule>) -> Result<(Bound,
     }
 }
 
+/// Parses an integral such as `Int_0^1 Int_0^x2 1 dx1 dx2`: the headers `Int_<from>^<to>`
+/// from the outermost integration, then `1`, then the variables from the innermost one.
 pub fn parse(source: &str) -> Result<IntegralSpec, String> {
     let res = IntegralParser::parse(Rule::input, source);
 
@@ -37,20 +41,36 @@ pub fn parse(source: &str) -> Result<IntegralSpec, String> {
         return Err(err.to_string());
     }
 
+    let mut int_parse = res.unwrap();
+    let integral = int_parse.next().unwrap().into_inner().next().unwrap();
+    parse_integral(integral)
+}
+
+/// Parses a weighted sum of integrals such as `2 * Int_0^1 Int_0^x1 1 dx0 dx1 + Int_0^1 1 dx0`
+/// (a single integral is a sum of one term of weight 1).
+pub fn parse_sum(source: &str) -> Result<Vec<WeightedIntegral>, String> {
+    let mut sum_parse = IntegralParser::parse(Rule::sum, source).map_err(|err| err.to_string())?;
+    let mut res = Vec::new();
+    for term in sum_parse.next().unwrap().into_inner().filter(|pair| pair.as_rule() == Rule::term) {
+        let mut parts = term.into_inner();
+        let mut part = parts.next().unwrap();
+        let mut weight = BigInt::from(1);
+        if part.as_rule() == Rule::weight {
+            weight = part.as_str().parse().unwrap();
+            part = parts.next().unwrap();
+        }
+        res.push(WeightedIntegral::new(weight, parse_integral(part)?));
+    }
+    Ok(res)
+}
+
+/// Builds the specification of a parsed `integral` rule.
+fn parse_integral(integral: pest::iterators::Pair<Rule>) -> Result<IntegralSpec, String> {
     let mut int_spec = crate::integral::IntegralSpec::new();
     let mut int_bounds: Vec<(Bound, Bound)> = Vec::new();
     let mut int_vars: Vec<usize> = Vec::new();
 
-    let mut int_parse = res.unwrap();
-    let parts = int_parse
-        .next()
-        .unwrap()
-        .into_inner()
-        .next()
-        .unwrap()
-        .into_inner();
-
-    for part in parts {
+    for part in integral.into_inner() {
         //println!("part = {part}");
         match part.as_rule() {
             Rule::int_header => match parse_bounds(part.into_inner()) {
@@ -91,7 +111,9 @@ pub fn parse(source: &str) -> Result<IntegralSpec, String> {
 
 #[cfg(test)]
 mod tests {
-    use super::parse;
+    use num::BigInt;
+
+    use super::{parse, parse_sum};
 
     #[test]
     fn test_parse_comments() {
@@ -130,4 +152,40 @@ mod tests {
         let spec_ko = parse("Int_0^1 Int_0^x2 Int_x2^1 Int_x2^1 Int_0^x2 1 dx4 dx3 dx1 dx2");
         assert!(spec_ko.is_err());
     }
+
+    #[test]
+    fn test_named_variables() {
+        let spec = parse("Int_0^1 Int_0^build_2 Int_compile^1 1 dlink dcompile dbuild_2").unwrap();
+        assert_eq!(spec.elements.len(), 3);
+        assert_eq!(spec.var_ref("compile".to_string()), 1);
+        assert_eq!(spec.to_string(), "Int_0^1 Int_0^build_2 Int_compile^1 1 dlink dcompile dbuild_2");
+        // the output of the BIT resolution has no spaces
+        assert_eq!(parse("Int_0^1Int_0^x2 1 dx1dx2").unwrap().elements.len(), 2);
+        assert!(parse("Int_0^1 1 d2a").is_err());
+        assert!(parse("Int_0^1 1 dx1a").is_err());
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let source = "Int_0^1 Int_0^x_3 Int_x_3^1 Int_x_3^1 Int_0^x_3 1 dx_1 dx_5 dx_4 dx_2 dx_3";
+        let spec = parse(source).unwrap();
+        assert_eq!(spec.to_string(), source);
+        let reparsed = parse(&spec.to_string()).unwrap();
+        assert_eq!(reparsed.var_map, spec.var_map);
+        assert_eq!(parse("1").unwrap().to_string(), "1");
+    }
+
+    #[test]
+    fn test_parse_sum() {
+        let sum = parse_sum("// split\n2 * Int_0^1 Int_0^x1 1 dx0 dx1 + Int_0^1 1 dx0").unwrap();
+        assert_eq!(sum.len(), 2);
+        assert_eq!(sum[0].weight, BigInt::from(2));
+        assert_eq!(sum[0].spec.elements.len(), 2);
+        assert_eq!(sum[1].weight, BigInt::from(1));
+        assert_eq!(crate::integral::format_sum(&sum), "2 * Int_0^1 Int_0^x1 1 dx0 dx1 + Int_0^1 1 dx0");
+        assert_eq!(parse_sum("1").unwrap()[0].spec.elements.len(), 0);
+        assert_eq!(parse_sum("Int_0^1Int_0^x2 1 dx1dx2").unwrap().len(), 1);
+        assert!(parse_sum("2 * ").is_err());
+        assert!(parse_sum("Int_0^1 1 dx0 +").is_err());
+    }
 }
@@ -0,0 +1,337 @@
+// This module reads a poset given by constraints such as `a < b, b < c, a < d` and counts
+// its linear extensions: the cover graph of each connected component is decomposed into an
+// integral by the BIT resolution (a weighted sum of integrals when it is not a forest), and
+// the integral of the poset, whose value is the probability that random reals respect the
+// order, is computed.
+
+use std::collections::{HashMap, HashSet};
+
+use num::{BigInt, BigRational};
+use regex::Regex;
+
+use crate::integral::{format_sum, Bound, IntegralSpec, WeightedIntegral};
+use crate::integrator::{integrate_sum, IntegrationOptions};
+use crate::maths::factorial;
+use crate::splitting::{split_resolution, MAX_INTEGRALS};
+use crate::transitive_reduction::{covers, transitive_closure};
+
+/// A finite poset given by its elements and some of its relations (the order is their
+/// transitive closure).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Poset {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    /// Pairs `(lower, upper)`
+    relations: Vec<(usize, usize)>,
+}
+
+/// Number of linear extensions of a poset, with the way it was computed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearExtensions {
+    /// The integral computed (a weighted sum of integrals if the poset is not a forest), over
+    /// variables named after the elements
+    pub integral: String,
+    /// Probability that uniform random reals are ordered as the poset
+    pub probability: BigRational,
+    /// Number of linear extensions (`#le`)
+    pub count: BigInt,
+}
+
+impl Poset {
+    pub fn new() -> Poset {
+        Poset::default()
+    }
+
+    /// Parses constraints separated by commas, semicolons or newlines. Each constraint is a
+    /// chain `a < b < c` (or `c > b > a`), or a single name to declare an isolated element.
+    /// Lines starting with `//` are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The constraints, e.g. `a < b, b < c, a < d`.
+    ///
+    /// # Returns
+    ///
+    /// The poset, or an error naming the faulty constraint.
+    pub fn parse(source: &str) -> Result<Poset, String> {
+        let name_re = Regex::new(r"^[A-Za-z0-9_]+$").unwrap();
+        let mut poset = Poset::new();
+        let lines = source.lines().filter(|line| !line.trim_start().starts_with("//"));
+        for constraint in lines.flat_map(|line| line.split([',', ';'])) {
+            let constraint = constraint.trim();
+            if constraint.is_empty() {
+                continue;
+            }
+            let bad = || format!("Bad constraint: {constraint}");
+            let (names, ascending) = match (constraint.contains('<'), constraint.contains('>')) {
+                (true, true) => return Err(bad()),
+                (_, false) => (constraint.split('<').collect::<Vec<&str>>(), true),
+                (false, true) => (constraint.split('>').collect::<Vec<&str>>(), false),
+            };
+            let mut elements = Vec::new();
+            for name in names {
+                let name = name.trim();
+                if !name_re.is_match(name) {
+                    return Err(bad());
+                }
+                elements.push(poset.add_element(name));
+            }
+            for pair in elements.windows(2) {
+                match ascending {
+                    true => poset.add_relation(pair[0], pair[1]),
+                    false => poset.add_relation(pair[1], pair[0]),
+                }
+            }
+        }
+        Ok(poset)
+    }
+
+    /// Returns the index of the element `name`, adding it if it is new.
+    pub fn add_element(&mut self, name: &str) -> usize {
+        if let Some(&i) = self.index.get(name) {
+            return i;
+        }
+        self.names.push(name.to_string());
+        self.index.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    /// Adds the relation `lower < upper` between two element indices.
+    pub fn add_relation(&mut self, lower: usize, upper: usize) {
+        self.relations.push((lower, upper));
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Names of the elements, by index.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The relations `(lower, upper)` given, by element indices.
+    pub fn relations(&self) -> &[(usize, usize)] {
+        &self.relations
+    }
+
+    /// Computes the strict order relation: `closure[i][j]` is true when `i < j`.
+    /// Fails if the constraints contain a cycle.
+    pub fn closure(&self) -> Result<Vec<Vec<bool>>, String> {
+        let n = self.len();
+        let mut matrice = vec![vec![0; n]; n];
+        for &(lower, upper) in &self.relations {
+            matrice[lower][upper] = 1;
+        }
+        transitive_closure(&matrice).map_err(|i| format!("The constraints contain a cycle through {}", self.names[i]))
+    }
+
+    /// Computes the cover relation (the transitive reduction of the order) as an adjacency
+    /// matrix: `i` is covered by `j` when `i < j` with no element in between.
+    pub fn covers(&self) -> Result<Vec<Vec<i32>>, String> {
+        Ok(covers(&self.closure()?))
+    }
+
+    /// Splits the elements into the connected components of the comparability graph, each
+    /// one sorted, in the order of their smallest element.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut component: Vec<usize> = (0..self.len()).collect();
+        fn find(component: &mut [usize], i: usize) -> usize {
+            let mut root = i;
+            while component[root] != root {
+                root = component[root];
+            }
+            component[i] = root;
+            root
+        }
+        for &(lower, upper) in &self.relations {
+            let (a, b) = (find(&mut component, lower), find(&mut component, upper));
+            component[a.max(b)] = a.min(b);
+        }
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_of: HashMap<usize, usize> = HashMap::new();
+        for i in 0..self.len() {
+            let root = find(&mut component, i);
+            let group = *group_of.entry(root).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(i);
+        }
+        groups
+    }
+
+    /// Names of the variables standing for the elements in the integrals: the element name
+    /// when it is a valid variable name, else a sanitized version of it (`build-1` gives
+    /// `build_1`, `2` gives `_2`), made unique.
+    pub fn variable_names(&self) -> Vec<String> {
+        let x_var = Regex::new(r"^x_?[0-9]").unwrap();
+        let exact_x_var = Regex::new(r"^x_?[0-9]+$").unwrap();
+        let mut used = HashSet::new();
+        let mut res = Vec::new();
+        for name in &self.names {
+            let mut var: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+            let starts_ok = var.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+            if !starts_ok || (x_var.is_match(&var) && !exact_x_var.is_match(&var)) {
+                var.insert(0, '_');
+            }
+            while used.contains(&var) {
+                var.push('_');
+            }
+            used.insert(var.clone());
+            res.push(var);
+        }
+        res
+    }
+
+    /// Builds the integrals of the poset: the cover graph of each connected component is
+    /// decomposed with the BIT resolution, split into a weighted sum of integrals when it is
+    /// not a forest (see `split_resolution`), and the integrals of the components, which are
+    /// independent, are nested one in the other. The variables are named after the elements
+    /// (see `variable_names`).
+    ///
+    /// # Returns
+    ///
+    /// The integrals with their weights, whose sum is the probability that uniform random
+    /// reals are ordered as the poset.
+    pub fn integrals(&self) -> Result<Vec<WeightedIntegral>, String> {
+        self.integrals_with(|matrice| split_resolution(matrice, MAX_INTEGRALS))
+    }
+
+    /// Same as `integrals`, the cover graph of each component being decomposed by `decompose`
+    /// instead of `split_resolution`.
+    ///
+    /// # Arguments
+    ///
+    /// * `decompose` - Turns the adjacency matrix of a component into integrals over its
+    ///   nodes `x0, x1, ...`, with their weights.
+    pub fn integrals_with<F>(&self, decompose: F) -> Result<Vec<WeightedIntegral>, String>
+    where
+        F: Fn(&[Vec<i32>]) -> Result<Vec<WeightedIntegral>, String>,
+    {
+        let covers = self.covers()?;
+        let var_re = Regex::new(r"x(\d+)").unwrap();
+        let var_names = self.variable_names();
+        let mut res = vec![WeightedIntegral::new(BigInt::from(1), IntegralSpec::new())];
+        for elements in self.components() {
+            let sous_matrice: Vec<Vec<i32>> =
+                elements.iter().map(|&i| elements.iter().map(|&j| covers[i][j]).collect()).collect();
+            let component = decompose(&sous_matrice).map_err(|e| {
+                let names: Vec<&str> = elements.iter().map(|&i| self.names[i].as_str()).collect();
+                format!("Cannot decompose the component {{{}}}: {e}", names.join(", "))
+            })?;
+            // from the indices in the component to the names of the elements
+            let rename = |name: &str| match var_re.captures(name) {
+                Some(caps) => var_names[elements[caps[1].parse::<usize>().unwrap()]].clone(),
+                None => name.to_string(),
+            };
+            let rename_bound = |bound: &Bound| match bound {
+                Bound::Var(name) => Bound::Var(rename(name)),
+                bound => bound.clone(),
+            };
+            let mut produit = Vec::new();
+            for outer in &component {
+                for inner in &res {
+                    let mut spec = inner.spec.clone();
+                    for (var, b1, b2) in &outer.spec.elements {
+                        let var = spec.register_var(rename(&outer.spec.var_name(*var)));
+                        spec.push(var, rename_bound(b1), rename_bound(b2));
+                    }
+                    produit.push(WeightedIntegral::new(&inner.weight * &outer.weight, spec));
+                }
+            }
+            res = produit;
+        }
+        Ok(res)
+    }
+
+    /// The integrals of the poset (see `integrals`) as a sum, e.g. `2 * Int_0^1 ... + Int_0^1 ...`.
+    pub fn integral(&self) -> Result<String, String> {
+        Ok(format_sum(&self.integrals()?))
+    }
+
+    /// Counts the linear extensions of the poset.
+    pub fn linear_extensions(&self, options: &IntegrationOptions) -> Result<LinearExtensions, String> {
+        let integrals = self.integrals()?;
+        let probability = integrate_sum(&integrals, options).map_err(|e| e.to_string())?;
+        let count = (probability.clone() * BigRational::from_integer(factorial(self.len()))).to_integer();
+        Ok(LinearExtensions {
+            integral: format_sum(&integrals),
+            probability,
+            count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::BigInt;
+
+    use super::Poset;
+    use crate::splitting::split_resolution;
+    use crate::integrator::IntegrationOptions;
+
+    fn count(source: &str) -> Result<BigInt, String> {
+        let options = IntegrationOptions {
+            quiet: true,
+            ..IntegrationOptions::default()
+        };
+        Poset::parse(source)?.linear_extensions(&options).map(|le| le.count)
+    }
+
+    #[test]
+    fn test_parse() {
+        let poset = Poset::parse("a < b < c; d > b\n// comment\ne").unwrap();
+        assert_eq!(poset.names(), ["a", "b", "c", "d", "e"]);
+        assert_eq!(poset.relations, vec![(0, 1), (1, 2), (1, 3)]);
+        assert!(Poset::parse("a < b > c").is_err());
+        assert!(Poset::parse("a <= b").is_err());
+        assert!(Poset::parse("a < ").is_err());
+        assert_eq!(Poset::parse("").unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_covers_and_components() {
+        let poset = Poset::parse("a < b, b < c, a < c, d").unwrap();
+        assert_eq!(
+            poset.covers().unwrap(),
+            vec![vec![0, 1, 0, 0], vec![0, 0, 1, 0], vec![0, 0, 0, 0], vec![0, 0, 0, 0]]
+        );
+        assert_eq!(poset.components(), vec![vec![0, 1, 2], vec![3]]);
+        let err = Poset::parse("a < b, b < c, c < a").unwrap().closure().unwrap_err();
+        assert!(err.contains("cycle"), "{err}");
+    }
+
+    #[test]
+    fn test_linear_extensions() {
+        // chain, tree, V, N, disjoint union of two chains of 2
+        assert_eq!(count("a < b < c < d"), Ok(BigInt::from(1)));
+        assert_eq!(count("a < b, a < c, a < d, b < e"), Ok(BigInt::from(12)));
+        assert_eq!(count("a < c, b < c"), Ok(BigInt::from(2)));
+        assert_eq!(count("a < c, b < c, b < d"), Ok(BigInt::from(5)));
+        assert_eq!(count("a < b, c < d"), Ok(BigInt::from(6)));
+        assert_eq!(count("a, b, c"), Ok(BigInt::from(6)));
+        assert_eq!(count(""), Ok(BigInt::from(1)));
+        // K2,2, split into a < b and b < a (2 extensions each), times 5 places for e
+        assert_eq!(count("e, a < c, a < d, b < c, b < d"), Ok(BigInt::from(20)));
+        let integral = Poset::parse("a < c, a < d, b < c, b < d").unwrap().integral().unwrap();
+        assert_eq!(integral.matches(" + ").count(), 1, "{integral}");
+        let err = Poset::parse("e, a < c, a < d, b < c, b < d")
+            .unwrap()
+            .integrals_with(|matrice| split_resolution(matrice, 1))
+            .unwrap_err();
+        assert_eq!(err, "Cannot decompose the component {a, c, d, b}: The DAG splits into more than 1 integrals");
+    }
+
+    #[test]
+    fn test_variable_names() {
+        let poset = Poset::parse("compile < link, 2 < x1, x1a < _2").unwrap();
+        assert_eq!(poset.variable_names(), ["compile", "link", "_2", "x1", "_x1a", "_2_"]);
+        let integral = Poset::parse("a < b, c").unwrap().integral().unwrap();
+        assert_eq!(integral, "Int_0^1 Int_0^1 Int_0^b 1 da db dc");
+    }
+}
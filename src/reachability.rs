@@ -0,0 +1,206 @@
+// This module answers the order queries on a DAG — is `i` below `j`, are they comparable,
+// which elements are above or below, minimal, maximal, or covering each other — from its
+// transitive closure, computed once. It also checks that an integral given by a decomposition
+// describes exactly the order of the DAG.
+
+use regex::Regex;
+
+use crate::integral::{Bound, IntegralSpec};
+use crate::transitive_reduction::{covers, transitive_closure};
+
+/// The strict order of a DAG, `i < j` when there is a path from `i` to `j`, with its cover
+/// relation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reachability {
+    closure: Vec<Vec<bool>>,
+    covers: Vec<Vec<i32>>,
+}
+
+impl Reachability {
+    /// Computes the order of a DAG.
+    ///
+    /// # Arguments
+    ///
+    /// * `matrice` - The adjacency matrix of the DAG.
+    ///
+    /// # Returns
+    ///
+    /// The order, or an error naming a node on a cycle.
+    pub fn new(matrice: &[Vec<i32>]) -> Result<Reachability, String> {
+        let closure = transitive_closure(matrice).map_err(|i| format!("The graph contains a cycle through x{i}"))?;
+        let covers = covers(&closure);
+        Ok(Reachability { closure, covers })
+    }
+
+    pub fn len(&self) -> usize {
+        self.closure.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.closure.is_empty()
+    }
+
+    /// Whether `i < j`, that is whether there is a path from `i` to `j`.
+    pub fn less_than(&self, i: usize, j: usize) -> bool {
+        self.closure[i][j]
+    }
+
+    /// Whether `i < j` or `j < i`.
+    pub fn comparable(&self, i: usize, j: usize) -> bool {
+        self.closure[i][j] || self.closure[j][i]
+    }
+
+    /// Whether `j` covers `i`: `i < j` with no element in between.
+    pub fn covers(&self, i: usize, j: usize) -> bool {
+        self.covers[i][j] == 1
+    }
+
+    /// The up-set generated by `i`: `i` and the elements above it, in increasing indices.
+    pub fn up_set(&self, i: usize) -> Vec<usize> {
+        (0..self.len()).filter(|&j| j == i || self.closure[i][j]).collect()
+    }
+
+    /// The down-set generated by `i`: `i` and the elements below it, in increasing indices.
+    pub fn down_set(&self, i: usize) -> Vec<usize> {
+        (0..self.len()).filter(|&j| j == i || self.closure[j][i]).collect()
+    }
+
+    /// The elements covering `i`.
+    pub fn upper_covers(&self, i: usize) -> Vec<usize> {
+        (0..self.len()).filter(|&j| self.covers(i, j)).collect()
+    }
+
+    /// The elements covered by `i`.
+    pub fn lower_covers(&self, i: usize) -> Vec<usize> {
+        (0..self.len()).filter(|&j| self.covers(j, i)).collect()
+    }
+
+    /// The elements with nothing below them (the sources of the DAG).
+    pub fn minimal_elements(&self) -> Vec<usize> {
+        (0..self.len()).filter(|&j| (0..self.len()).all(|i| !self.closure[i][j])).collect()
+    }
+
+    /// The elements with nothing above them (the sinks of the DAG).
+    pub fn maximal_elements(&self) -> Vec<usize> {
+        (0..self.len()).filter(|&i| !self.closure[i].contains(&true)).collect()
+    }
+
+    /// The relation matrix of the order, `closure[i][j]` being true when `i < j`.
+    pub fn closure(&self) -> &[Vec<bool>] {
+        &self.closure
+    }
+
+    /// The adjacency matrix of the cover graph (the transitive reduction of the DAG).
+    pub fn cover_graph(&self) -> &[Vec<i32>] {
+        &self.covers
+    }
+
+    /// Checks that an integral over the variables `x0, x1, ...`, as given by the BIT
+    /// resolution, describes the order: the bounds of the integrations, `x_i < x_j` for a
+    /// lower bound `x_i` of `x_j` or an upper bound `x_j` of `x_i`, must generate exactly the
+    /// relations of the DAG, so that the integral is the volume of its order polytope.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The integral.
+    ///
+    /// # Returns
+    ///
+    /// Nothing, or the first variable or relation which does not match.
+    pub fn check_integral(&self, spec: &IntegralSpec) -> Result<(), String> {
+        let var_re = Regex::new(r"^x_?(\d+)$").unwrap();
+        let n = self.len();
+        let noeud = |name: &str| match var_re.captures(name).and_then(|caps| caps[1].parse::<usize>().ok()) {
+            Some(i) if i < n => Ok(i),
+            _ => Err(format!("The variable {name} is not a node of the DAG")),
+        };
+        if spec.elements.len() != n {
+            return Err(format!("The integral has {} variables for {n} nodes", spec.elements.len()));
+        }
+        let mut contraintes = vec![vec![0; n]; n];
+        for (var, b1, b2) in &spec.elements {
+            let x = noeud(&spec.var_name(*var))?;
+            if let Bound::Var(name) = b1 {
+                contraintes[noeud(name)?][x] = 1;
+            }
+            if let Bound::Var(name) = b2 {
+                contraintes[x][noeud(name)?] = 1;
+            }
+        }
+        let ordre = Reachability::new(&contraintes).map_err(|e| format!("The bounds of the integral are cyclic: {e}"))?;
+        for i in 0..n {
+            for j in 0..n {
+                match (self.less_than(i, j), ordre.less_than(i, j)) {
+                    (true, false) => return Err(format!("The integral does not ensure x{i} < x{j}")),
+                    (false, true) => return Err(format!("The integral adds x{i} < x{j}")),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Reachability;
+    use crate::families::{boolean_lattice, crown};
+    use crate::generator_matrix::{random_arity_matrix_generator, rng_from_seed};
+    use crate::parser::parse;
+    use crate::resolution_using_lists::list_BIT_resolution;
+
+    #[test]
+    fn test_queries() {
+        // 0 -> 1 -> 3, 0 -> 2 -> 3, 0 -> 3, and 4 alone
+        let matrice = vec![
+            vec![0, 1, 1, 1, 0],
+            vec![0, 0, 0, 1, 0],
+            vec![0, 0, 0, 1, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+        ];
+        let ordre = Reachability::new(&matrice).unwrap();
+        assert!(ordre.less_than(0, 3) && !ordre.less_than(3, 0) && !ordre.less_than(1, 1));
+        assert!(ordre.comparable(3, 0) && !ordre.comparable(1, 2) && !ordre.comparable(4, 0));
+        assert!(ordre.covers(1, 3) && !ordre.covers(0, 3));
+        assert_eq!(ordre.up_set(1), vec![1, 3]);
+        assert_eq!(ordre.down_set(3), vec![0, 1, 2, 3]);
+        assert_eq!(ordre.upper_covers(0), vec![1, 2]);
+        assert_eq!(ordre.lower_covers(3), vec![1, 2]);
+        assert_eq!(ordre.minimal_elements(), vec![0, 4]);
+        assert_eq!(ordre.maximal_elements(), vec![3, 4]);
+        assert_eq!(ordre.cover_graph()[0], vec![0, 1, 1, 0, 0]);
+
+        // the subsets of 3 elements: the empty set is below all the others
+        let ordre = Reachability::new(&boolean_lattice(3)).unwrap();
+        assert_eq!(ordre.up_set(0).len(), 8);
+        assert_eq!(ordre.down_set(0b110), vec![0, 0b010, 0b100, 0b110]);
+        assert_eq!(ordre.minimal_elements(), vec![0]);
+        assert_eq!(Reachability::new(&crown(3)).unwrap().maximal_elements(), vec![3, 4, 5]);
+
+        assert_eq!(Reachability::new(&[vec![0, 1], vec![1, 0]]).unwrap_err(), "The graph contains a cycle through x0");
+    }
+
+    #[test]
+    fn test_check_integral() {
+        let mut rng = rng_from_seed(4);
+        for size in 2..20 {
+            let matrice = random_arity_matrix_generator(size, &mut rng);
+            let spec = parse(&list_BIT_resolution(matrice.clone()).unwrap()).unwrap();
+            assert_eq!(Reachability::new(&matrice).unwrap().check_integral(&spec), Ok(()));
+        }
+        // 0 -> 1, 0 -> 2
+        let ordre = Reachability::new(&[vec![0, 1, 1], vec![0, 0, 0], vec![0, 0, 0]]).unwrap();
+        assert_eq!(ordre.check_integral(&parse("Int_0^1 Int_x0^1 Int_x0^1 1 dx2 dx1 dx0").unwrap()), Ok(()));
+        assert_eq!(
+            ordre.check_integral(&parse("Int_0^1 Int_x0^1 Int_0^1 1 dx2 dx1 dx0").unwrap()),
+            Err("The integral does not ensure x0 < x2".to_string())
+        );
+        assert_eq!(
+            ordre.check_integral(&parse("Int_0^1 Int_x0^1 Int_x1^1 1 dx2 dx1 dx0").unwrap()),
+            Err("The integral adds x1 < x2".to_string())
+        );
+        assert!(ordre.check_integral(&parse("Int_0^1 Int_x0^1 1 dx1 dx0").unwrap()).is_err());
+        assert!(ordre.check_integral(&parse("Int_0^1 Int_x0^1 Int_x0^1 1 dx2 dx1 dx5").unwrap()).is_err());
+    }
+}
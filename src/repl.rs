@@ -0,0 +1,285 @@
+// This module implements the interactive mode (`rustegrator repl`): integrals are typed
+// one after another, their results can be bound to names and combined with arithmetic.
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use num::{BigInt, BigRational, Zero};
+
+use crate::integrator::{integrate_sum, sum_nbvars, IntegrationOptions};
+use crate::{maths, parser};
+
+const HELP: &str = "\
+Type an integral (Int_0^1 Int_0^x2 1 dx1 dx2), a weighted sum of integrals (2 * Int_... + Int_...)
+or an expression over results (a / b, (a + b) * 2).
+  name = <integral or expression>   bind a result to a name (the last result is always `_`)
+  :formula on|off                   show the formula at each integration step
+  :stats on|off                     show the statistics table after each integration
+  :le on|off                        also show the number of linear extensions
+  :vars                             list the bound names
+  :help                             show this help
+  :quit                             leave";
+
+pub struct Repl {
+    vars: HashMap<String, BigRational>,
+    options: IntegrationOptions,
+    le: bool,
+}
+
+impl Repl {
+    pub fn new(options: IntegrationOptions, le: bool) -> Repl {
+        Repl {
+            vars: HashMap::new(),
+            options,
+            le,
+        }
+    }
+
+    /// Reads lines from `input` until the end of input or `:quit`.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: &mut R, out: &mut W) -> Result<(), String> {
+        let io_error = |e: std::io::Error| format!("REPL I/O error: {e}");
+        writeln!(out, "Type :help for help, :quit to leave").map_err(io_error)?;
+        loop {
+            write!(out, "> ").map_err(io_error)?;
+            out.flush().map_err(io_error)?;
+            let mut line = String::new();
+            if input.read_line(&mut line).map_err(io_error)? == 0 {
+                return Ok(());
+            }
+            let line = line.trim();
+            if line == ":quit" || line == ":q" {
+                return Ok(());
+            }
+            match self.eval_line(line) {
+                Ok(Some(msg)) => writeln!(out, "{msg}").map_err(io_error)?,
+                Ok(None) => {}
+                Err(e) => writeln!(out, "Error: {e}").map_err(io_error)?,
+            }
+        }
+    }
+
+    /// Evaluates one line, returning the message to print.
+    pub fn eval_line(&mut self, line: &str) -> Result<Option<String>, String> {
+        if line.is_empty() || line.starts_with("//") {
+            return Ok(None);
+        }
+        if let Some(command) = line.strip_prefix(':') {
+            return self.command(command).map(Some);
+        }
+
+        let (name, source) = match line.split_once('=') {
+            Some((name, source)) if is_identifier(name.trim()) => (Some(name.trim()), source.trim()),
+            _ => (None, line),
+        };
+        // an integral, or a sum of integrals as written by `decompose --split`
+        let (value, nbvars) = if source.contains("Int_") {
+            let sum = parser::parse_sum(source)?;
+            let mut options = self.options.clone();
+            options.quiet = !(options.formula || options.stats);
+            let res = integrate_sum(&sum, &options).map_err(|e| e.to_string())?;
+            (res, Some(sum_nbvars(&sum)))
+        } else {
+            (Expr::new(source, &self.vars).parse()?, None)
+        };
+
+        let mut msg = match name {
+            Some(name) => format!("{name} = {value}"),
+            None => value.to_string(),
+        };
+        if let (true, Some(nbvars)) = (self.le, nbvars) {
+            msg.push_str(&format!("   (#le = {})", value.clone() * maths::factorial(nbvars)));
+        }
+        if let Some(name) = name {
+            self.vars.insert(name.to_string(), value.clone());
+        }
+        self.vars.insert("_".to_string(), value);
+        Ok(Some(msg))
+    }
+
+    fn command(&mut self, command: &str) -> Result<String, String> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let flag = |value: &str| match value {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(format!("Expected on or off, found {value}")),
+        };
+        match words.as_slice() {
+            ["formula", value] => {
+                self.options.formula = flag(value)?;
+                Ok(format!("formula {value}"))
+            }
+            ["stats", value] => {
+                self.options.stats = flag(value)?;
+                Ok(format!("stats {value}"))
+            }
+            ["le", value] => {
+                self.le = flag(value)?;
+                Ok(format!("le {value}"))
+            }
+            ["vars"] => {
+                let mut names: Vec<&String> = self.vars.keys().collect();
+                names.sort();
+                Ok(names
+                    .iter()
+                    .map(|name| format!("{name} = {}", self.vars[*name]))
+                    .collect::<Vec<String>>()
+                    .join("\n"))
+            }
+            ["help"] | ["h"] => Ok(HELP.to_string()),
+            _ => Err(format!("Unknown command :{command} (type :help)")),
+        }
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+/// Recursive descent evaluator for expressions made of rationals, names, `+ - * /` and parentheses.
+struct Expr<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    vars: &'a HashMap<String, BigRational>,
+}
+
+impl<'a> Expr<'a> {
+    fn new(source: &str, vars: &'a HashMap<String, BigRational>) -> Expr<'a> {
+        Expr {
+            chars: source.chars().collect(),
+            pos: 0,
+            vars,
+        }
+    }
+
+    fn parse(mut self) -> Result<BigRational, String> {
+        let res = self.sum()?;
+        match self.peek() {
+            None => Ok(res),
+            Some(c) => Err(format!("Unexpected '{c}' at position {}", self.pos + 1)),
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn sum(&mut self) -> Result<BigRational, String> {
+        let mut res = self.product()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.product()?;
+            res = if op == '+' { res + rhs } else { res - rhs };
+        }
+        Ok(res)
+    }
+
+    fn product(&mut self) -> Result<BigRational, String> {
+        let mut res = self.atom()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.atom()?;
+            if op == '*' {
+                res *= rhs;
+            } else if rhs.is_zero() {
+                return Err("Division by zero".to_string());
+            } else {
+                res /= rhs;
+            }
+        }
+        Ok(res)
+    }
+
+    fn atom(&mut self) -> Result<BigRational, String> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let res = self.sum()?;
+                match self.peek() {
+                    Some(')') => {
+                        self.pos += 1;
+                        Ok(res)
+                    }
+                    _ => Err("Missing ')'".to_string()),
+                }
+            }
+            Some('-') => {
+                self.pos += 1;
+                Ok(-self.atom()?)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.pos < self.chars.len() && self.chars[self.pos].is_ascii_digit() {
+                    self.pos += 1;
+                }
+                let digits: String = self.chars[start..self.pos].iter().collect();
+                let n = BigInt::from_str(&digits).map_err(|e| e.to_string())?;
+                Ok(BigRational::from_integer(n))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let start = self.pos;
+                while self.pos < self.chars.len() && (self.chars[self.pos].is_ascii_alphanumeric() || self.chars[self.pos] == '_') {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                self.vars.get(&name).cloned().ok_or_else(|| format!("Unknown name: {name}"))
+            }
+            Some(c) => Err(format!("Unexpected '{c}' at position {}", self.pos + 1)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::Repl;
+    use crate::integrator::IntegrationOptions;
+
+    #[test]
+    fn test_bind_and_compute() {
+        let mut repl = Repl::new(IntegrationOptions::default(), false);
+        assert_eq!(repl.eval_line("a = Int_0^1 Int_0^x2 1 dx1 dx2"), Ok(Some("a = 1/2".to_string())));
+        assert_eq!(
+            repl.eval_line("b = Int_0^1 Int_0^x3 Int_x3^1 Int_x3^1 Int_0^x3 1 dx1 dx5 dx4 dx2 dx3"),
+            Ok(Some("b = 1/30".to_string()))
+        );
+        assert_eq!(repl.eval_line("a / b"), Ok(Some("15".to_string())));
+        assert_eq!(repl.eval_line("(a + 1) * -2 - _"), Ok(Some("-18".to_string())));
+        assert!(repl.eval_line("a / 0").is_err());
+        assert!(repl.eval_line("c + 1").is_err());
+        assert!(repl.eval_line("a +").is_err());
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut repl = Repl::new(IntegrationOptions::default(), false);
+        let mut out = Vec::new();
+        let input = ":le on\nx = Int_0^1 Int_0^x2 1 dx1 dx2\n:vars\n:stats maybe\n:quit\nignored\n";
+        repl.run(&mut Cursor::new(input), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("x = 1/2   (#le = 1)"));
+        assert!(out.contains("_ = 1/2\nx = 1/2"));
+        assert!(out.contains("Error: Expected on or off, found maybe"));
+        assert!(!out.contains("ignored"));
+    }
+
+    #[test]
+    fn test_weighted_sum() {
+        let mut repl = Repl::new(IntegrationOptions::default(), true);
+        assert_eq!(
+            repl.eval_line("s = 2 * Int_0^1 Int_0^x2 1 dx1 dx2 + Int_0^1 Int_x1^1 1 dx2 dx1"),
+            Ok(Some("s = 3/2   (#le = 3)".to_string()))
+        );
+        assert_eq!(repl.eval_line("s * 2"), Ok(Some("3".to_string())));
+        assert!(repl.eval_line("2 * Int_0^1").is_err());
+    }
+}
@@ -2,12 +2,14 @@
 // It supports operations such as adjacency resolution, transitive reduction, and displaying the adjacency matrix.
 
 use std::collections::HashMap;
-use crate::construire_pred;
-use crate::transitive_reduction::{create_ordre_topologique, transitive_reduction_topologique};
+use crate::bitmatrix::BitMatrix;
+use crate::matrix::construire_adj;
+use crate::resolution_using_lists::{fin_resolution, BitCase, BitError, BitStep};
+use crate::transitive_reduction::transitive_reduction;
 
 
 /// Represents a directed graph with adjacency and predecessor lists.
-pub(crate) struct Graph {
+pub struct Graph {
     adj: HashMap<usize, Vec<usize>>,
     pred: HashMap<usize, Vec<usize>>,
     size: usize
@@ -21,18 +23,42 @@ impl Graph {
     /// * `adj` - A hashmap where each key is a vertex identifier and its value is a vector of vertices that can be reached from the key vertex.
     /// * `pred` - A hashmap similar to `adj` but each key's vector represents vertices that can reach the key vertex.
     /// * `size` - The number of vertices in the graph.
-    pub(crate) fn new(adj: HashMap<usize, Vec<usize>>, pred: HashMap<usize, Vec<usize>>, size: usize) -> Self {
+    pub fn new(adj: HashMap<usize, Vec<usize>>, pred: HashMap<usize, Vec<usize>>, size: usize) -> Self {
         Graph { adj, pred, size }
     }
 
+    /// Constructs the graph of an adjacency matrix.
+    pub fn from_matrice(matrice: Vec<Vec<i32>>) -> Self {
+        let (pred, size) = (predecesseurs(&matrice), matrice.len());
+        Graph::new(construire_adj(matrice), pred, size)
+    }
+
 
     /// Resolves the adjacency relationships in the graph to simplify its structure based on transitive reduction.
     ///
     /// # Returns
     ///
-    /// A string that represents the changes made to the graph, encoded in a special format.
-    pub(crate) fn resolution_adjacence(&mut self) -> String {
-        let mut lastmodified_line = 0;
+    /// A string that represents the changes made to the graph, encoded in a special format,
+    /// or the residual graph if some nodes have several predecessors or successors.
+    pub fn resolution_adjacence(&mut self) -> Result<String, BitError> {
+        self.resolution(None)
+    }
+
+    /// Same as `resolution_adjacence`, also returning the graph after each step.
+    ///
+    /// # Returns
+    ///
+    /// The integral (or the residual graph) and the steps of the resolution, in order.
+    pub fn resolution_steps(&mut self) -> (Result<String, BitError>, Vec<BitStep>) {
+        let mut steps = Vec::new();
+        let integral = self.resolution(Some(&mut steps));
+        (integral, steps)
+    }
+
+    fn resolution(&mut self, mut steps: Option<&mut Vec<BitStep>>) -> Result<String, BitError> {
+        // a redundant edge would count as a predecessor
+        self.transitive_reduction();
+        let mut integres = vec![false; self.size];
         let mut prefixe = " 1 ".to_string();
         let mut suffixe = String::new();
         let mut cpt = 0;
@@ -52,11 +78,12 @@ impl Graph {
                     if preds.is_empty() && sortante == 1 {
                         prefixe = format!("Int_0^x{}{}", index_sortante, prefixe);
                         suffixe += &format!("dx{}", cpt);
-                        lastmodified_line = index_sortante;
+                        integres[cpt] = true;
                         self.adj.insert(cpt, vec![0; self.adj.len()]);
                         if let Some(pred_list) = self.pred.get_mut(&index_sortante) {
                             pred_list.retain(|&x| x != cpt);
                         }
+                        self.record_step(&mut steps, BitCase::Top, cpt, format!("Int_0^x{}", index_sortante));
                         cpt = 0;
 
                         continue;
@@ -64,13 +91,14 @@ impl Graph {
                     // Bottom Case
                     else if preds.len() == 1 && sortante == 0 {
                         let pred_node = preds[0];
-                        lastmodified_line = preds[0];
+                        integres[cpt] = true;
                         if let Some(pred_succs) = self.adj.get_mut(&pred_node) {
                             pred_succs[cpt] = 0; // Enlever le lien du prédécesseur vers ce nœud
                         }
                         self.pred.insert(cpt, vec![]);
                         prefixe = format!("Int_x{}^1{}", pred_node, prefixe);
                         suffixe += &format!("dx{}", cpt);
+                        self.record_step(&mut steps, BitCase::Bottom, cpt, format!("Int_x{}^1", pred_node));
                         cpt = 0;
                         continue;
                     } else if preds.len() == 1 && sortante == 1 {
@@ -99,9 +127,10 @@ impl Graph {
                         // Mise à jour des préfixes et suffixes
                         prefixe = format!("Int_x{}^x{}{}", var, index_sortante, prefixe);
                         suffixe += &format!("dx{}", cpt);
-                        lastmodified_line = cpt;
-                        cpt = 0; // Réinitialiser la boucle
+                        integres[cpt] = true;
                         self.transitive_reduction();
+                        self.record_step(&mut steps, BitCase::Intermediate, cpt, format!("Int_x{}^x{}", var, index_sortante));
+                        cpt = 0; // Réinitialiser la boucle
                         continue;
                     }
                 }
@@ -109,10 +138,24 @@ impl Graph {
             cpt += 1;
         }
 
+        fin_resolution(&prefixe, &suffixe, &integres, self.matrice())
+    }
 
-        prefixe = "Int_0^1".to_owned() + &*prefixe;
-        suffixe = suffixe + "dx" + &lastmodified_line.to_string();
-        return prefixe + &suffixe;
+    /// Appends a step to `steps`, if the steps are recorded.
+    fn record_step(&self, steps: &mut Option<&mut Vec<BitStep>>, case: BitCase, node: usize, header: String) {
+        if let Some(steps) = steps {
+            steps.push(BitStep { case, node, header, matrice: self.matrice() });
+        }
+    }
+
+    /// Returns the adjacency matrix of the graph.
+    pub fn matrice(&self) -> Vec<Vec<i32>> {
+        (0..self.size)
+            .map(|i| match self.adj.get(&i) {
+                Some(row) => row.iter().map(|&v| v as i32).collect(),
+                None => vec![0; self.size],
+            })
+            .collect()
     }
 
     /// Displays the adjacency matrix of the graph.
@@ -132,107 +175,76 @@ impl Graph {
         }
     }
 
-    /// Performs a transitive reduction using a simplified Floyd-Warshall algorithm adapted for graphs represented with hash maps.
-    /// We don't use it in the project, it doens't work for long paths
-     fn transitive_reduction_floyd_warshall(&mut self) {
-        // Use a temporary copy of the adjacency list for calculations without altering the original during iteration
-        let adj_copy = self.adj.clone();
-
-        for k in 0..self.size {
-            if let Some(nodes_reachable_from_k) = adj_copy.get(&k) {
-                for &i in nodes_reachable_from_k {
-                    if let Some(succs_of_i) = self.adj.get_mut(&i) {
-                        for &j in nodes_reachable_from_k {
-                            if i != j && succs_of_i.contains(&k) && nodes_reachable_from_k.contains(&j) {
-                                // If a path i -> k -> j exists, then the direct connection i -> j is redundant
-                                succs_of_i.remove(j); // Remove the direct edge i -> j
-                                if let Some(preds_of_j) = self.pred.get_mut(&j) {
-                                    preds_of_j.retain(|&x| x != i); // Remove i from the predecessors of j
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    /// Performs transitive reduction on the graph. This function first computes a topological order
-    /// of the graph and then uses it to perform the transitive reduction.
-    ///
-    /// # Process
-    /// 1. Compute the topological order of the graph.
-    /// 2. Use the topological order to identify and remove redundant edges.
-    /// 3. Rebuild the adjacency list based on the reduced graph.
-    /// 4. Reconstruct the predecessor list using the updated adjacency list.
+    /// Performs transitive reduction on the graph, then rebuilds the predecessor lists. A
+    /// cycle is left as it is.
     fn transitive_reduction(&mut self) {
-        let ordre_topologique = self.create_ordre_topologique();
-        let ordre_reduit = transitive_reduction_topologique(&ordre_topologique);
-        for (_, v) in self.adj.iter_mut() {
-            *v = vec![0; self.size];
-        }
-        self.pred.clear();
-
-
-        for (noeud, arrivee) in ordre_reduit {
-            if let Some(adj_noeud) = self.adj.get_mut(&noeud) {
-                // Modifier la valeur dans adj et predif arrivee < self.size {
-                adj_noeud[arrivee] = 1;
+        if let Ok(reduction) = transitive_reduction(&self.matrice()) {
+            for (noeud, ligne) in self.adj.iter_mut() {
+                *ligne = reduction[*noeud].iter().map(|&v| v as usize).collect();
             }
+            self.pred = predecesseurs(&reduction);
         }
-        self.pred = construire_pred(&self.adj);
     }
 
-    /// Generates a topological order for the graph by constructing an adjacency matrix and then processing it.
-    ///
-    /// # Returns
-    /// A vector of tuples representing the topological order, where each tuple is a pair of vertices (source, target).
-    ///
-    /// # Example
-    /// Given a graph with adjacency list representation, this method constructs an adjacency matrix
-    /// and uses it to perform topological sorting.
-    fn create_ordre_topologique(&mut self) -> Vec<(usize, usize)> {
-        let mut matrice = Vec::new();
-
-        let mut keys: Vec<usize> = self.adj.keys().copied().collect();
-        keys.sort();
-
-        for key in keys {
-            if let Some(v) = self.adj.get(&key) {
-                let row: Vec<i32> = v.iter().map(|&x| x as i32).collect(); // chat gpt m'a aidé
-                //car j'avais un probleme de type vec<&vec<i32>> au lieu de vec<vec<i32>>
-                matrice.push(row);
-            }
-        }
-
-        create_ordre_topologique(matrice)
-    }
+}
 
+/// The predecessors of each node, read from the columns of the bit matrix of the graph.
+fn predecesseurs(matrice: &[Vec<i32>]) -> HashMap<usize, Vec<usize>> {
+    let graphe = BitMatrix::from_matrice(matrice);
+    (0..graphe.len()).map(|j| (j, graphe.predecessors(j).collect())).collect()
 }
 
+#[cfg(test)]
 mod tests {
-    use crate::construire_adj;
-    use crate::generator_matrix::{one_arity_matrix_generator, random_arity_matrix_generator};
+    use crate::generator_matrix::{random_arity_matrix_generator, rng_from_seed};
+    use crate::parser::parse;
+    use crate::reachability::Reachability;
     use super::*;
 
     #[test]
     fn test_resolution_hashmaps() {
+        let mut rng = rng_from_seed(5);
         for i in 4..30 {
             for _ in 0..10 {
                 println!("{}", i);
                 let size = i;
-                let matrice = random_arity_matrix_generator(size);
-                let u = matrice.len();
-                let adj = construire_adj(matrice);
-                let pred = construire_pred(&adj);
-                let mut g = Graph::new(adj, pred, u);
+                let matrice = random_arity_matrix_generator(size, &mut rng);
+                let ordre = Reachability::new(&matrice).unwrap();
+                let mut g = Graph::from_matrice(matrice);
 
-                g.resolution_adjacence();
+                let integral = g.resolution_adjacence().unwrap();
+                assert_eq!(ordre.check_integral(&parse(&integral).unwrap()), Ok(()), "{integral}");
                 assert!(g.verify_all_zero(), "Couldn't resolve the DAG using BIT decomposition");
             }
         }
     }
 
+    #[test]
+    fn test_resolution_steps() {
+        let mut rng = rng_from_seed(3);
+        for size in 4..30 {
+            let matrice = random_arity_matrix_generator(size, &mut rng);
+            let (integral, steps) = Graph::from_matrice(matrice.clone()).resolution_steps();
+            assert_eq!(integral, Graph::from_matrice(matrice).resolution_adjacence());
+            assert_eq!(steps.len(), size - 1);
+            assert!(steps[size - 2].matrice.iter().flatten().all(|&x| x == 0), "Unresolved DAG");
+        }
+    }
+
+    #[test]
+    fn test_undecomposable() {
+        // 0 -> 1 -> {2, 3}, 4 -> {2, 3}: the node 0 goes, the K2,2 stays
+        let matrice = vec![
+            vec![0, 1, 0, 0, 0],
+            vec![0, 0, 1, 1, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 1, 1, 0],
+        ];
+        let err = Graph::from_matrice(matrice.clone()).resolution_adjacence().unwrap_err();
+        assert_eq!(err, crate::resolution_using_lists::list_BIT_resolution(matrice).unwrap_err());
+    }
+
     // Fonction helper pour vérifier que tous les nœuds sont résolus
     impl Graph {
         pub fn verify_all_zero(&self) -> bool {
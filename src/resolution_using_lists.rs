@@ -1,198 +1,192 @@
 use std::collections::HashSet;
+use std::fmt;
 use regex::Regex;
-use crate::transitive_reduction::{apply_transitive_reduction, create_ordre_topologique, transitive_reduction_topologique,transitive_reduction_using_floyd_warshall};
-/// Resolves adjacency matrices by applying transitive reduction and formatting output for integrals.
-///
-/// # Arguments
-/// * `mut matrice` - A mutable reference to a vector of vector of i32s representing the adjacency matrix.
-///
-/// # Returns
-/// A formatted string representing the integral expressions derived from the adjacency matrix after resolution.
-pub(crate) fn list_BIT_resolution(mut matrice: Vec<Vec<i32>>) -> String {
-    let mut cpt = 0;
-    let mut lastmodified_line = 0;
-    let mut prefixe = " 1 ".to_string();
-    let mut suffixe = String::new();
-    while (cpt) < matrice.len() {
-        let ligne = &matrice[cpt];
-        let vne = verify_no_entry(matrice.clone(), cpt);
-        let mut sortante = 0;
-        let mut index_sortante = 0;
-        for (compte_valeur, &valeur) in ligne.iter().enumerate() {
-            if valeur == 1 {
-                sortante += 1;
-                index_sortante = compte_valeur;
-            }
-        }
-        // Top Case
-        if vne.0 == 0 && sortante == 1 {
-            delete_vortex(&mut matrice, cpt);
-            prefixe = ("Int_0^".to_owned() + "x" + &index_sortante.to_string() + &*prefixe);
-            suffixe = suffixe + "dx" + &cpt.to_string();
-            lastmodified_line = index_sortante;
-
-            cpt = 0;
-            continue;
-        }
-        //bottom case
-        if vne.0 == 1 && sortante == 0 {
-            //delete_edge(&mut matrice, cpt);
-            matrice[vne.1][cpt] = 0;
-            prefixe = ("Int_".to_owned() + "x" + &vne.1.to_string() + "^1" + &*prefixe);
-            suffixe = (suffixe + "dx" + &cpt.to_string());
-            lastmodified_line = vne.1;
-            cpt = 0;
-            continue;
+use crate::bitmatrix::BitMatrix;
+/// The three reductions of the BIT resolution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitCase {
+    /// A source with one successor, integrated from 0 to its successor
+    Top,
+    /// A sink with one predecessor, integrated from its predecessor to 1
+    Bottom,
+    /// A node with one predecessor and one successor, integrated between them
+    Intermediate,
+}
+
+/// One step of the BIT resolution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitStep {
+    pub case: BitCase,
+    /// The node integrated out
+    pub node: usize,
+    /// The integral added for it, e.g. `Int_x2^x5`
+    pub header: String,
+    /// The adjacency matrix left after the step
+    pub matrice: Vec<Vec<i32>>,
+}
+
+/// The residual graph of a DAG the BIT resolution cannot decompose: the nodes left all have
+/// several predecessors or successors (or there are several connected components).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitError {
+    /// The nodes that could not be integrated out
+    pub nodes: Vec<usize>,
+    /// The adjacency matrix left, over all the nodes of the DAG
+    pub residual: Vec<Vec<i32>>,
+}
+
+impl fmt::Display for BitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let noeuds: Vec<String> = self.nodes.iter().map(|i| format!("x{i}")).collect();
+        let mut aretes = Vec::new();
+        for (i, ligne) in self.residual.iter().enumerate() {
+            aretes.extend(ligne.iter().enumerate().filter(|(_, &v)| v == 1).map(|(j, _)| format!("x{i} -> x{j}")));
         }
-        //Intermediate case
-        if vne.0 == 1 && sortante == 1 {
-            matrice[vne.1][index_sortante] = 1;
-            matrice[vne.1][cpt] = 0;
-            delete_vortex(&mut matrice, cpt);
-            prefixe = ("Int_".to_owned() + "x" + &vne.1.to_string() + "^x" + &index_sortante.to_string() + &*prefixe);
-            suffixe = (suffixe + "dx" + &cpt.to_string());
-
-            lastmodified_line = cpt;
-            cpt = 0;
-            let ordre_topo = create_ordre_topologique(matrice.clone());
-            matrice = apply_transitive_reduction(matrice[0].len(), transitive_reduction_topologique(&ordre_topo));
-            continue;
+        write!(
+            f,
+            "The BIT resolution cannot decompose the DAG: {} nodes are left ({})",
+            self.nodes.len(),
+            noeuds.join(", ")
+        )?;
+        match aretes.is_empty() {
+            true => write!(f, " with no edges between them"),
+            false => write!(f, " with the edges {}", aretes.join(", ")),
         }
-        cpt += 1;
     }
-    prefixe = "Int_0^1".to_owned() + &*prefixe;
-    suffixe = suffixe + "dx" + &lastmodified_line.to_string();
-    return prefixe.to_owned() + &*suffixe;
 }
 
+impl std::error::Error for BitError {}
 
-/// Verifies if there are no entries in a matrix row, used to assist in resolving adjacency matrices.
+/// Checks the end of a resolution: the DAG is decomposed when a single node is left, which is
+/// then integrated from 0 to 1.
 ///
 /// # Arguments
-/// * `mut matrice` - A mutable reference to a vector of vector of i32s representing the adjacency matrix.
-/// * `line_to_verify` - The line index to verify for entries.
+/// * `prefixe` - The integrals of the nodes integrated out, from the outermost.
+/// * `suffixe` - Their variables, from the innermost.
+/// * `integres` - Which nodes were integrated out.
+/// * `residual` - The adjacency matrix left.
 ///
 /// # Returns
-/// A tuple where the first element indicates the number of entries, and the second the line index.
-fn verify_no_entry(mut matrice: Vec<Vec<i32>>, line_to_verify: usize) -> (i32, usize) {
-    let mut res = 0;
-    let mut res_ligne_entrante = 0;
-    for (cpt_lignes, ligne) in matrice.iter().enumerate() {
-        if cpt_lignes == line_to_verify {
-            continue;
-        } else {
-            if ligne[line_to_verify] == 1 {
-                res += 1;
-                res_ligne_entrante = cpt_lignes;
-            }
-        }
+/// The integral, or the residual graph.
+pub(crate) fn fin_resolution(prefixe: &str, suffixe: &str, integres: &[bool], residual: Vec<Vec<i32>>) -> Result<String, BitError> {
+    let nodes: Vec<usize> = (0..integres.len()).filter(|&i| !integres[i]).collect();
+    match nodes[..] {
+        [] => Ok("1".to_string()),
+        [dernier] => Ok(format!("Int_0^1{prefixe}{suffixe}dx{dernier}")),
+        _ => Err(BitError { nodes, residual }),
     }
-    (res, res_ligne_entrante)
 }
 
-/// Deletes a specific edge in an adjacency matrix, setting it to zero.
+/// Resolves adjacency matrices by applying transitive reduction and formatting output for integrals.
 ///
 /// # Arguments
-/// * `matrice` - A mutable reference to a vector of vector of i32s representing the adjacency matrix.
-/// * `line_to_delete` - The line index where the edge should be deleted.
-fn delete_edge(matrice: &mut Vec<Vec<i32>>, line_to_delete: usize) {
-    let mut cpt = 0;
-    for ligne in matrice {
-        cpt += 1;
-        if (ligne[line_to_delete] == 1) {
-            ligne[line_to_delete] = 0;
-        }
-    }
+/// * `mut matrice` - A mutable reference to a vector of vector of i32s representing the adjacency matrix.
+///
+/// # Returns
+/// A formatted string representing the integral expressions derived from the adjacency matrix after resolution,
+/// or the residual graph if some nodes have several predecessors or successors in the transitive reduction.
+pub fn list_BIT_resolution(matrice: Vec<Vec<i32>>) -> Result<String, BitError> {
+    resolution(matrice, None)
 }
 
-/// Deletes all outgoing edges from a node in the matrix, effectively isolating it.
+/// Same as `list_BIT_resolution`, also returning the graph after each step.
 ///
 /// # Arguments
-/// * `matrice` - A mutable reference to a vector of vector of i32s representing the adjacency matrix.
-/// * `line_to_delete` - The line index of the node to isolate.
-fn delete_vortex(matrice: &mut Vec<Vec<i32>>, line_to_delete: usize) {
-    if line_to_delete < matrice.len() {
-        matrice[line_to_delete] = vec![0; matrice[line_to_delete].len()];
-    }
+/// * `matrice` - The adjacency matrix of the DAG.
+///
+/// # Returns
+/// The integral (or the residual graph) and the steps of the resolution, in order.
+pub fn resolution_steps(matrice: Vec<Vec<i32>>) -> (Result<String, BitError>, Vec<BitStep>) {
+    let mut steps = Vec::new();
+    let integral = resolution(matrice, Some(&mut steps));
+    (integral, steps)
 }
 
-
-
-
-
-
-
-#[cfg(test)]
-mod tests {
-    fn bit_decomposition_test(mut matrice: Vec<Vec<i32>>) -> (String, Vec<Vec<i32>>) {
-        let mut cpt = 0;
-        let mut lastmodified_line = 0;
-        let mut prefixe = " 1 ".to_string();
-        let mut suffixe = String::new();
-        while (cpt) < matrice.len() {
-            let ligne = &matrice[cpt];
-            let vne = verify_no_entry(matrice.clone(), cpt);
-            let mut sortante = 0;
-            let mut index_sortante = 0;
-            for (compte_valeur, &valeur) in ligne.iter().enumerate() {
-                if valeur == 1 {
-                    sortante += 1;
-                    index_sortante = compte_valeur;
-                }
-            }
+fn resolution(matrice: Vec<Vec<i32>>, mut steps: Option<&mut Vec<BitStep>>) -> Result<String, BitError> {
+    let mut graphe = BitMatrix::from_matrice(&matrice);
+    // a redundant edge would count as a predecessor (the order is the same, a cycle is left
+    // as it is); the graph then stays reduced
+    if let Ok(reduction) = graphe.transitive_reduction() {
+        graphe = reduction;
+    }
+    let n = graphe.len();
+    let mut cpt = 0;
+    let mut integres = vec![false; n];
+    let mut entetes: Vec<String> = Vec::new();
+    let mut suffixe = String::new();
+    while cpt < n {
+        let (entrees, sortantes) = (graphe.in_degree(cpt), graphe.out_degree(cpt));
+        let (case, header) = if entrees == 0 && sortantes == 1 {
             // Top Case
-            if vne.0 == 0 && sortante == 1 {
-                delete_vortex(&mut matrice, cpt);
-                prefixe = ("Int_0^".to_owned() + "x" + &index_sortante.to_string() + &*prefixe);
-                suffixe = suffixe + "dx" + &cpt.to_string();
-                lastmodified_line = index_sortante;
-
-                cpt = 0;
-                continue;
-            }
+            let successeur = graphe.successors(cpt).next().unwrap();
+            graphe.unset(cpt, successeur);
+            (BitCase::Top, format!("Int_0^x{successeur}"))
+        } else if entrees == 1 && sortantes == 0 {
             //bottom case
-            if vne.0 == 1 && sortante == 0 {
-                //delete_edge(&mut matrice, cpt);
-                matrice[vne.1][cpt] = 0;
-                prefixe = ("Int_".to_owned() + "x" + &vne.1.to_string() + "^1" + &*prefixe);
-                suffixe = (suffixe + "dx" + &cpt.to_string());
-                lastmodified_line = vne.1;
-                cpt = 0;
-                continue;
-            }
+            let predecesseur = graphe.predecessors(cpt).next().unwrap();
+            graphe.unset(predecesseur, cpt);
+            (BitCase::Bottom, format!("Int_x{predecesseur}^1"))
+        } else if entrees == 1 && sortantes == 1 {
             //Intermediate case
-            if vne.0 == 1 && sortante == 1 {
-                matrice[vne.1][index_sortante] = 1;
-                matrice[vne.1][cpt] = 0;
-                delete_vortex(&mut matrice, cpt);
-                prefixe = ("Int_".to_owned() + "x" + &vne.1.to_string() + "^x" + &index_sortante.to_string() + &*prefixe);
-                suffixe = (suffixe + "dx" + &cpt.to_string());
-
-                lastmodified_line = cpt;
-                cpt = 0;
-                let ordre_topo = create_ordre_topologique(matrice.clone());
-                matrice = apply_transitive_reduction(matrice[0].len(), transitive_reduction_topologique(&ordre_topo));
-                continue;
+            let predecesseur = graphe.predecessors(cpt).next().unwrap();
+            let successeur = graphe.successors(cpt).next().unwrap();
+            graphe.isolate(cpt);
+            // the new edge is redundant if there is another path
+            if !graphe.reaches(predecesseur, successeur) {
+                graphe.set(predecesseur, successeur);
             }
+            (BitCase::Intermediate, format!("Int_x{predecesseur}^x{successeur}"))
+        } else {
             cpt += 1;
+            continue;
+        };
+        suffixe = suffixe + "dx" + &cpt.to_string();
+        integres[cpt] = true;
+        if let Some(steps) = steps.as_deref_mut() {
+            steps.push(BitStep {
+                case,
+                node: cpt,
+                header: header.clone(),
+                matrice: graphe.to_matrice(),
+            });
         }
-        prefixe = "Int_0^1".to_owned() + &*prefixe;
-        suffixe = suffixe + "dx" + &lastmodified_line.to_string();
-        (prefixe.to_owned() + &*suffixe, matrice)
+        entetes.push(header);
+        cpt = 0;
+    }
+    let prefixe: String = entetes.iter().rev().map(String::as_str).chain([" 1 "]).collect();
+    fin_resolution(&prefixe, &suffixe, &integres, graphe.to_matrice())
+}
+
+#[cfg(test)]
+mod tests {
+    /// Checks that the BIT resolution of a DAG removes all its edges and gives an integral
+    /// of the order of the DAG.
+    fn verifier_resolution(matrice: Vec<Vec<i32>>) {
+        let ordre = Reachability::new(&matrice).unwrap();
+        let (integral, steps) = resolution_steps(matrice);
+        let integral = integral.unwrap();
+        assert_eq!(ordre.check_integral(&parse(&integral).unwrap()), Ok(()), "{integral}");
+        assert!(steps.last().is_none_or(|step| verify_all_zero(step.matrice.clone())), "Unresolved matrix");
     }
-    use crate::generator_matrix::{add_node, one_arity_matrix_generator, random_arity_matrix_generator};
-    use crate::verify_all_zero;
+
+    use crate::generator_matrix::{add_node, erdos_renyi_generator, random_arity_matrix_generator, rng_from_seed};
+    use crate::parser::parse;
+    use crate::reachability::Reachability;
+    use crate::resolution_using_hashmap::Graph;
+    use crate::matrix::verify_all_zero;
+    use crate::transitive_reduction::transitive_reduction;
+    use rand::Rng;
     use super::*;
 
     #[test]
     fn test_generer_matrice_arite_un() {
+        let mut rng = rng_from_seed(1);
         for i in 4..30 {
             for _ in 0..10{
                 println!("{}",i);
                 let size = i; // Taille de la matrice pour le test
-                let matrice = random_arity_matrix_generator(size);
-                let (_, matrice_apres) = bit_decomposition_test(matrice);
-                assert!(verify_all_zero(matrice_apres), "Unresolved matrix");
+                let matrice = random_arity_matrix_generator(size, &mut rng);
+                verifier_resolution(matrice);
             }
         }
     }
@@ -200,24 +194,117 @@ mod tests {
 
     #[test]
     fn test_generer_matrice_arite_hasard() {
+        let mut rng = rng_from_seed(2);
         for i in 4..30 {
             for _ in 0..10{
                 println!("{}",i);
                 let size = i; // Taille de la matrice pour le test
-                let matrice = random_arity_matrix_generator(size);
-                let (_, matrice_apres) = bit_decomposition_test(matrice);
-                assert!(verify_all_zero(matrice_apres), "Unresolved matrix");
+                let matrice = random_arity_matrix_generator(size, &mut rng);
+                verifier_resolution(matrice);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolution_steps() {
+        let mut rng = rng_from_seed(3);
+        for i in 4..30 {
+            let matrice = random_arity_matrix_generator(i, &mut rng);
+            let (integral, steps) = resolution_steps(matrice.clone());
+            assert_eq!(integral, list_BIT_resolution(matrice));
+            assert_eq!(steps.len(), i - 1);
+            assert!(verify_all_zero(steps[i - 2].matrice.clone()), "Unresolved matrix");
+        }
+        assert_eq!(resolution_steps(vec![vec![0, 1], vec![0, 0]]).0, Ok("Int_0^1Int_0^x1 1 dx0dx1".to_string()));
+        // 3 <- 1 -> 0 -> 2: the middle node 0, then the leaves of 1
+        let matrice = vec![vec![0, 0, 1, 0], vec![1, 0, 0, 1], vec![0, 0, 0, 0], vec![0, 0, 0, 0]];
+        let (_, steps) = resolution_steps(matrice);
+        assert_eq!(steps[0].case, BitCase::Intermediate);
+        assert_eq!((steps[0].node, steps[0].header.as_str()), (0, "Int_x1^x2"));
+        assert_eq!(steps[0].matrice[1], vec![0, 0, 1, 1]);
+        assert_eq!((steps[1].case, steps[1].node), (BitCase::Bottom, 2));
+    }
+
+    #[test]
+    fn test_undecomposable() {
+        assert_eq!(list_BIT_resolution(vec![]), Ok("1".to_string()));
+        assert_eq!(list_BIT_resolution(vec![vec![0]]), Ok("Int_0^1 1 dx0".to_string()));
+        // 0 -> 1 -> {2, 3}, 4 -> {2, 3}: the node 0 goes, the K2,2 stays
+        let matrice = vec![
+            vec![0, 1, 0, 0, 0],
+            vec![0, 0, 1, 1, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 1, 1, 0],
+        ];
+        let err = list_BIT_resolution(matrice).unwrap_err();
+        assert_eq!(err.nodes, vec![1, 2, 3, 4]);
+        assert_eq!(err.residual[0], vec![0; 5]);
+        assert_eq!(
+            err.to_string(),
+            "The BIT resolution cannot decompose the DAG: 4 nodes are left (x1, x2, x3, x4) \
+             with the edges x1 -> x2, x1 -> x3, x4 -> x2, x4 -> x3"
+        );
+        let err = list_BIT_resolution(vec![vec![0; 2]; 2]).unwrap_err();
+        assert!(err.to_string().ends_with("(x0, x1) with no edges between them"), "{err}");
+    }
+
+    #[test]
+    fn test_redundant_edges() {
+        // 1 -> 0, 2 -> 0, 3 -> 0, 4 -> 0 and 1 -> 3, 2 -> 1, 2 -> 3, 4 -> 3: the edges into 0 but
+        // 3 -> 0, and 2 -> 3, are implied by the others
+        let matrice = vec![
+            vec![0, 0, 0, 0, 0],
+            vec![1, 0, 0, 1, 0],
+            vec![1, 1, 0, 1, 0],
+            vec![1, 0, 0, 0, 0],
+            vec![1, 0, 0, 1, 0],
+        ];
+        let integral = list_BIT_resolution(matrice.clone()).unwrap();
+        assert_eq!(integral, list_BIT_resolution(transitive_reduction(&matrice).unwrap()).unwrap());
+        assert_eq!(Ok(integral.clone()), Graph::from_matrice(matrice.clone()).resolution_adjacence());
+        let ordre = Reachability::new(&matrice).unwrap();
+        assert_eq!(ordre.check_integral(&parse(&integral).unwrap()), Ok(()), "{integral}");
+    }
+
+    #[test]
+    fn test_large_dags() {
+        // thousands of nodes: the degrees are kept by the bit matrix, no matrix is copied
+        let matrice = random_arity_matrix_generator(2000, &mut rng_from_seed(5));
+        let integral = list_BIT_resolution(matrice.clone()).unwrap();
+        let spec = parse(&integral).unwrap();
+        assert_eq!(spec.elements.len(), 2000);
+        assert_eq!(Reachability::new(&matrice).unwrap().check_integral(&spec), Ok(()));
+
+        // the same result as the resolution on hashmaps and as the resolution of the
+        // transitive reduction, even with redundant edges
+        for seed in 0..200 {
+            let mut rng = rng_from_seed(seed);
+            let taille = rng.random_range(3..=10);
+            let matrice = erdos_renyi_generator(taille, rng.random_range(0.1..0.6), &mut rng);
+            let reduction = transitive_reduction(&matrice).unwrap();
+            let resultat = list_BIT_resolution(matrice.clone());
+            assert_eq!(resultat, Graph::from_matrice(matrice.clone()).resolution_adjacence(), "seed {seed}");
+            assert_eq!(resultat, list_BIT_resolution(reduction), "seed {seed}");
+            match resultat {
+                Ok(integral) => {
+                    let ordre = Reachability::new(&matrice).unwrap();
+                    assert_eq!(ordre.check_integral(&parse(&integral).unwrap()), Ok(()), "seed {seed}");
+                }
+                // only cover edges are left
+                Err(e) => assert_eq!(transitive_reduction(&e.residual).unwrap(), e.residual, "seed {seed}"),
             }
         }
     }
 
     #[test]
     fn test_ajout_noeud_matrice() {
+        let mut rng = rng_from_seed(4);
         for i in 4..30 {
             for _ in 0..10 {
-                let mut matrice = random_arity_matrix_generator(i); // Generate une matrice initiale d'arité 1
+                let mut matrice = random_arity_matrix_generator(i, &mut rng); // Generate une matrice initiale d'arité 1
                 assert_eq!(matrice.len(), i, "The initial size is incorrect");
-                add_node(&mut matrice); // Ajouter un nouveau nœud
+                add_node(&mut matrice, &mut rng); // Ajouter un nouveau nœud
                 assert_eq!(matrice.len(), i+1, "Couldn't add the node");
                 assert!(matrice.iter().all(|row| row.len() == i+1), "Lines are not the right length after adding");
             }
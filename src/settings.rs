@@ -0,0 +1,176 @@
+// This module loads the default options of the command line from the configuration
+// files (`rustegrator.toml` in the user configuration directory, then in the current
+// directory) and the `RUSTEGRATOR_*` environment variables, each layer overriding the
+// previous one. The command line flags override them all (see `main`).
+
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use config::{Config, Environment, File, FileFormat};
+use serde::Deserialize;
+
+pub const CONFIG_FILE_NAME: &str = "rustegrator.toml";
+
+/// How results are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// The value of the integral, as a fraction
+    #[default]
+    Fraction,
+    /// The number of linear extensions
+    Le,
+}
+
+/// Default options read from the configuration files and environment. An unknown key of a
+/// file is an error; the environment variables of other keys are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub output: OutputFormat,
+    pub quiet: bool,
+    pub formula: bool,
+    pub stats: bool,
+    pub stats_csv: Option<String>,
+    pub history_file: Option<PathBuf>,
+    /// Set to false to disable the run log
+    pub history: Option<bool>,
+    pub max_monomials: Option<usize>,
+    /// Memory limit, with the syntax of `--max-memory` (e.g. "4G")
+    pub max_memory: Option<String>,
+    /// Timeout in seconds
+    pub timeout: Option<u64>,
+    pub jobs: Option<usize>,
+    pub checkpoint_interval: Option<u64>,
+    pub cache: Option<bool>,
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// The keys of `Settings`, read from the `RUSTEGRATOR_<KEY>` environment variables.
+const KEYS: [&str; 14] = [
+    "output",
+    "quiet",
+    "formula",
+    "stats",
+    "stats_csv",
+    "history_file",
+    "history",
+    "max_monomials",
+    "max_memory",
+    "timeout",
+    "jobs",
+    "checkpoint_interval",
+    "cache",
+    "cache_dir",
+];
+
+/// Returns the user-level configuration file: `$XDG_CONFIG_HOME/rustegrator/rustegrator.toml`
+/// or `$HOME/.config/rustegrator/rustegrator.toml`.
+pub fn user_config_file() -> Option<PathBuf> {
+    match (env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME")) {
+        (Some(dir), _) if !dir.is_empty() => Some(Path::new(&dir).join("rustegrator").join(CONFIG_FILE_NAME)),
+        (_, Some(home)) if !home.is_empty() => {
+            Some(Path::new(&home).join(".config").join("rustegrator").join(CONFIG_FILE_NAME))
+        }
+        _ => None,
+    }
+}
+
+impl Settings {
+    /// Loads the settings from the user-level file, the project-level file (or `explicit`
+    /// instead of both when given) and the environment.
+    pub fn load(explicit: Option<&Path>) -> Result<Settings, String> {
+        let files = match explicit {
+            Some(path) => vec![(path.to_path_buf(), true)],
+            None => {
+                let mut files: Vec<(PathBuf, bool)> = user_config_file().into_iter().map(|p| (p, false)).collect();
+                files.push((PathBuf::from(CONFIG_FILE_NAME), false));
+                files
+            }
+        };
+        Settings::load_from(&files, None)
+    }
+
+    /// Loads the settings from `files` (path, required) in increasing priority, then from the
+    /// environment (`env` replaces the process environment when given). Only the variables
+    /// of the keys of `Settings` are read, as others (`RUSTEGRATOR_LOG`...) may be set for
+    /// other tools.
+    pub fn load_from(files: &[(PathBuf, bool)], env: Option<HashMap<String, String>>) -> Result<Settings, String> {
+        let mut builder = Config::builder();
+        for (path, required) in files {
+            builder = builder.add_source(File::from(path.as_path()).format(FileFormat::Toml).required(*required));
+        }
+        let variables: HashMap<String, String> = env
+            .unwrap_or_else(|| env::vars().collect())
+            .into_iter()
+            .filter(|(nom, _)| {
+                nom.strip_prefix("RUSTEGRATOR_")
+                    .is_some_and(|cle| KEYS.contains(&cle.to_lowercase().as_str()))
+            })
+            .collect();
+        builder = builder.add_source(
+            Environment::with_prefix("RUSTEGRATOR")
+                .prefix_separator("_")
+                .try_parsing(true)
+                .source(Some(variables)),
+        );
+        builder
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .map_err(|e| format!("Bad configuration: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{OutputFormat, Settings};
+
+    #[test]
+    fn test_layers() {
+        let dir = env::temp_dir().join(format!("rustegrator-test-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let user = dir.join("user.toml");
+        let project = dir.join("project.toml");
+        fs::write(&user, "output = \"le\"\njobs = 2\nmax_memory = \"4G\"\nstats = true\n").unwrap();
+        fs::write(&project, "jobs = 8\ntimeout = 60\n").unwrap();
+        let env = HashMap::from([("RUSTEGRATOR_TIMEOUT".to_string(), "5".to_string())]);
+
+        let files = vec![(user, true), (project, true), (dir.join("missing.toml"), false)];
+        let settings = Settings::load_from(&files, Some(env)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(settings.output, OutputFormat::Le);
+        assert_eq!(settings.jobs, Some(8));
+        assert_eq!(settings.timeout, Some(5));
+        assert_eq!(settings.max_memory, Some("4G".to_string()));
+        assert!(settings.stats);
+        assert!(!settings.formula);
+        assert_eq!(settings.cache, None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let missing = vec![(PathBuf::from("/nonexistent/rustegrator.toml"), true)];
+        assert!(Settings::load_from(&missing, Some(HashMap::new())).is_err());
+        assert_eq!(Settings::load_from(&[], Some(HashMap::new())), Ok(Settings::default()));
+
+        // the other variables of the prefix are ignored, not the unknown keys of a file
+        let env = HashMap::from([
+            ("RUSTEGRATOR_LOG".to_string(), "debug".to_string()),
+            ("RUSTEGRATOR_JOBS".to_string(), "3".to_string()),
+        ]);
+        assert_eq!(Settings::load_from(&[], Some(env)).unwrap().jobs, Some(3));
+        let dir = env::temp_dir().join(format!("rustegrator-test-settings-errors-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("unknown.toml");
+        fs::write(&file, "log = \"debug\"\n").unwrap();
+        let res = Settings::load_from(&[(file, true)], Some(HashMap::new()));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(res.unwrap_err().contains("unknown field `log`"));
+    }
+}
@@ -0,0 +1,190 @@
+// This module extends the BIT resolution to any DAG. When the resolution stops on nodes with
+// several predecessors (or successors), the maximum (or minimum) of their bounds is expanded
+// into cases: for two incomparable predecessors a and b, the order polytope is the union of
+// the polytopes of the orders where a < b and where b < a, which only meet on a null set.
+// Splitting until the BIT resolution succeeds gives integrals whose sum is the volume; the
+// integrals equal up to the names of their variables are kept once, with a weight.
+
+use std::collections::HashMap;
+
+use num::BigInt;
+
+use crate::integral::{Bound, IntegralSpec, WeightedIntegral};
+use crate::parser;
+use crate::resolution_using_lists::{list_BIT_resolution, BitError};
+use crate::transitive_reduction::{covers, transitive_closure};
+
+/// Default maximal number of integrals of a split (see `split_resolution`).
+pub const MAX_INTEGRALS: usize = 10_000;
+
+/// Decomposes any DAG into a weighted sum of integrals. The DAG is split into orders the BIT
+/// resolution can decompose, by adding a relation between two incomparable nodes at a time,
+/// one way then the other.
+///
+/// # Arguments
+///
+/// * `matrice` - The adjacency matrix of the DAG.
+/// * `max_integrals` - The maximal number of integrals, counted before the equal ones are
+///   merged; the number of cases can grow like the number of linear extensions.
+///
+/// # Returns
+///
+/// The integrals with their weights, whose sum is the volume of the order polytope (`#le /
+/// n!`): a single integral of weight 1 when the BIT resolution decomposes the DAG. Fails if
+/// the graph has a cycle or splits into too many integrals.
+pub fn split_resolution(matrice: &[Vec<i32>], max_integrals: usize) -> Result<Vec<WeightedIntegral>, String> {
+    let mut res: Vec<WeightedIntegral> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let ordre = transitive_closure(matrice).map_err(|i| format!("The graph contains a cycle through x{i}"))?;
+    let mut pile = vec![ordre];
+    let mut integrales = 0;
+    while let Some(ordre) = pile.pop() {
+        match list_BIT_resolution(covers(&ordre)) {
+            Ok(integral) => {
+                let spec = parser::parse(&integral).map_err(|e| format!("Bad decomposition {integral}: {e}"))?;
+                let forme = forme_canonique(&spec);
+                match index.get(&forme) {
+                    Some(&i) => res[i].weight += 1,
+                    None => {
+                        index.insert(forme, res.len());
+                        res.push(WeightedIntegral::new(BigInt::from(1), spec));
+                    }
+                }
+                integrales += 1;
+            }
+            Err(e) => {
+                let (a, b) = paire_a_separer(&e, &ordre)?;
+                pile.push(ajouter_relation(&ordre, b, a));
+                pile.push(ajouter_relation(&ordre, a, b));
+            }
+        }
+        if integrales + pile.len() > max_integrals {
+            return Err(format!("The DAG splits into more than {max_integrals} integrals"));
+        }
+    }
+    Ok(res)
+}
+
+/// Chooses the two incomparable nodes to order: two predecessors of a node left by the
+/// resolution (the cases of the maximum of its lower bounds), else two of its successors (the
+/// minimum of its upper bounds), else two of the nodes left, in different components.
+fn paire_a_separer(e: &BitError, ordre: &[Vec<bool>]) -> Result<(usize, usize), String> {
+    let residual = &e.residual;
+    let mut groupes: Vec<Vec<usize>> = Vec::new();
+    groupes.extend(e.nodes.iter().map(|&j| e.nodes.iter().copied().filter(|&i| residual[i][j] == 1).collect()));
+    groupes.extend(e.nodes.iter().map(|&i| e.nodes.iter().copied().filter(|&j| residual[i][j] == 1).collect()));
+    groupes.push(e.nodes.clone());
+    for groupe in &groupes {
+        for (k, &a) in groupe.iter().enumerate() {
+            if let Some(&b) = groupe[k + 1..].iter().find(|&&b| !ordre[a][b] && !ordre[b][a]) {
+                return Ok((a, b));
+            }
+        }
+    }
+    Err(e.to_string())
+}
+
+/// The order with the relation `a < b` added, and all the relations it implies.
+fn ajouter_relation(ordre: &[Vec<bool>], a: usize, b: usize) -> Vec<Vec<bool>> {
+    let mut res = ordre.to_vec();
+    for (i, ligne) in res.iter_mut().enumerate().filter(|(i, _)| *i == a || ordre[*i][a]) {
+        ligne[b] = true;
+        for (i_j, &b_j) in ligne.iter_mut().zip(&ordre[b]) {
+            *i_j |= b_j;
+        }
+        debug_assert!(!ligne[i], "ordering comparable nodes");
+    }
+    res
+}
+
+/// Form of an integral which does not depend on the names of its variables: the bounds of
+/// each integration, the variables being numbered in the order of integration.
+fn forme_canonique(spec: &IntegralSpec) -> String {
+    let position: HashMap<usize, usize> = spec.elements.iter().enumerate().map(|(k, (var, _, _))| (*var, k)).collect();
+    let borne = |bound: &Bound| match bound {
+        Bound::Var(name) => format!("x{}", position[&spec.var_ref(name.clone())]),
+        bound => bound.to_string(),
+    };
+    let formes: Vec<String> = spec.elements.iter().map(|(_, b1, b2)| format!("{}^{}", borne(b1), borne(b2))).collect();
+    formes.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{BigInt, BigRational};
+
+    use super::split_resolution;
+    use crate::families::{
+        antichain, binary_tree, binary_tree_extensions, boolean_lattice, boolean_lattice_extensions, crown, fence,
+        grid, hook_length,
+    };
+    use crate::generator_matrix::{erdos_renyi_generator, rng_from_seed};
+    use crate::integral::format_sum;
+    use crate::integrator::{integrate_sum, IntegrationOptions};
+    use crate::maths::factorial;
+    use crate::parser::parse;
+    use crate::resolution_using_lists::list_BIT_resolution;
+
+    fn extensions(matrice: &[Vec<i32>]) -> BigInt {
+        let options = IntegrationOptions {
+            quiet: true,
+            ..IntegrationOptions::default()
+        };
+        let integrals = split_resolution(matrice, 100_000).unwrap();
+        let res = integrate_sum(&integrals, &options).unwrap();
+        (res * BigRational::from_integer(factorial(matrice.len()))).to_integer()
+    }
+
+    #[test]
+    fn test_split() {
+        // decomposable DAGs give the integral of the BIT resolution
+        let matrice = binary_tree(3);
+        let integrals = split_resolution(&matrice, 1).unwrap();
+        assert_eq!(format_sum(&integrals), parse(&list_BIT_resolution(matrice.clone()).unwrap()).unwrap().to_string());
+        assert_eq!(extensions(&matrice), binary_tree_extensions(3));
+        assert_eq!(extensions(&fence(6)), BigInt::from(61));
+        assert_eq!(format_sum(&split_resolution(&[], 1).unwrap()), "1");
+
+        // K2,2: its 4 linear extensions are 2 orders of the same shape
+        let integrals = split_resolution(&crown(2), 10).unwrap();
+        assert_eq!(integrals.len(), 1);
+        assert_eq!(integrals[0].weight, BigInt::from(2));
+        assert_eq!(extensions(&crown(2)), BigInt::from(4));
+        assert_eq!(extensions(&antichain(5)), BigInt::from(120));
+
+        assert_eq!(extensions(&crown(3)), BigInt::from(48));
+        assert_eq!(extensions(&grid(2, 3)), hook_length(&[3, 3]));
+        assert_eq!(extensions(&grid(3, 3)), hook_length(&[3, 3, 3]));
+        assert_eq!(Some(extensions(&boolean_lattice(3))), boolean_lattice_extensions(3));
+    }
+
+    #[test]
+    fn test_random_dags() {
+        // number of orders of the subsets of nodes closed downwards
+        let compter = |matrice: &[Vec<i32>]| {
+            let n = matrice.len();
+            let mut compte = vec![BigInt::from(0); 1 << n];
+            compte[0] = BigInt::from(1);
+            for s in 0..1usize << n {
+                for j in (0..n).filter(|&j| s & (1 << j) == 0 && (0..n).all(|i| matrice[i][j] == 0 || s & (1 << i) != 0)) {
+                    let valeur = compte[s].clone();
+                    compte[s | 1 << j] += valeur;
+                }
+            }
+            compte[(1 << n) - 1].clone()
+        };
+        for seed in 0..20 {
+            let matrice = erdos_renyi_generator(7, 0.3, &mut rng_from_seed(seed));
+            assert_eq!(extensions(&matrice), compter(&matrice), "seed {seed}: {matrice:?}");
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            split_resolution(&[vec![0, 1], vec![1, 0]], 10).unwrap_err(),
+            "The graph contains a cycle through x0"
+        );
+        assert_eq!(split_resolution(&antichain(5), 10).unwrap_err(), "The DAG splits into more than 10 integrals");
+    }
+}
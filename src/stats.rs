@@ -0,0 +1,228 @@
+// This module collects statistics about the polynomial at each integration step
+// (size, degrees, coefficient bit sizes, memory, time) and renders them
+// as an aligned table or as a CSV file.
+
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::Duration;
+
+/// Size measures of a polynomial at a given point of the integration.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PolyStats {
+    pub nb_monos: usize,
+    pub nb_coefs: usize,
+    pub max_degree: i64,
+    pub max_numer_bits: u64,
+    pub max_denom_bits: u64,
+    pub memory_bytes: usize,
+}
+
+impl PolyStats {
+    /// Keeps, for each measure, the largest value between `self` and `other`.
+    pub fn peak(&mut self, other: &PolyStats) {
+        self.nb_monos = self.nb_monos.max(other.nb_monos);
+        self.nb_coefs = self.nb_coefs.max(other.nb_coefs);
+        self.max_degree = self.max_degree.max(other.max_degree);
+        self.max_numer_bits = self.max_numer_bits.max(other.max_numer_bits);
+        self.max_denom_bits = self.max_denom_bits.max(other.max_denom_bits);
+        self.memory_bytes = self.memory_bytes.max(other.memory_bytes);
+    }
+}
+
+/// Statistics of one integration step: the variable eliminated, the time it took
+/// and the size of the resulting polynomial.
+#[derive(Debug, Clone)]
+pub struct StepStats {
+    pub step: usize,
+    pub var: String,
+    pub time: Duration,
+    pub poly: PolyStats,
+}
+
+/// Statistics of a whole run, step by step, with the peak values reached.
+#[derive(Debug, Clone, Default)]
+pub struct RunStats {
+    pub steps: Vec<StepStats>,
+    pub peak: PolyStats,
+    pub total_time: Duration,
+}
+
+const HEADERS: [&str; 9] = [
+    "step", "var", "time", "#monomials", "#coefficients", "max_degree", "numer_bits", "denom_bits", "memory",
+];
+
+impl RunStats {
+    pub fn new() -> RunStats {
+        RunStats::default()
+    }
+
+    pub fn push(&mut self, step: StepStats) {
+        self.peak.peak(&step.poly);
+        self.total_time += step.time;
+        self.steps.push(step);
+    }
+
+    /// Returns the first step which produced the largest polynomial (in number of monomials).
+    pub fn blowup_step(&self) -> Option<&StepStats> {
+        self.steps.iter().max_by_key(|s| (s.poly.nb_monos, Reverse(s.step)))
+    }
+
+    fn rows(&self) -> Vec<[String; 9]> {
+        self.steps
+            .iter()
+            .map(|s| {
+                [
+                    s.step.to_string(),
+                    s.var.clone(),
+                    format!("{:.3?}", s.time),
+                    s.poly.nb_monos.to_string(),
+                    s.poly.nb_coefs.to_string(),
+                    s.poly.max_degree.to_string(),
+                    s.poly.max_numer_bits.to_string(),
+                    s.poly.max_denom_bits.to_string(),
+                    format_bytes(s.poly.memory_bytes),
+                ]
+            })
+            .collect()
+    }
+
+    /// Renders the statistics as an aligned table, with a final line for the peak values.
+    pub fn to_table(&self) -> String {
+        let mut rows = self.rows();
+        rows.push([
+            "peak".to_string(),
+            String::new(),
+            format!("{:.3?}", self.total_time),
+            self.peak.nb_monos.to_string(),
+            self.peak.nb_coefs.to_string(),
+            self.peak.max_degree.to_string(),
+            self.peak.max_numer_bits.to_string(),
+            self.peak.max_denom_bits.to_string(),
+            format_bytes(self.peak.memory_bytes),
+        ]);
+
+        let mut widths: Vec<usize> = HEADERS.iter().map(|h| h.len()).collect();
+        for row in &rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+
+        let format_row = |cells: Vec<&str>| -> String {
+            cells
+                .iter()
+                .enumerate()
+                .map(|(i, c)| format!("{:>width$}", c, width = widths[i]))
+                .collect::<Vec<String>>()
+                .join("  ")
+        };
+
+        let mut res = format_row(HEADERS.to_vec());
+        res.push('\n');
+        res.push_str(&"-".repeat(res.len() - 1));
+        for row in &rows {
+            res.push('\n');
+            res.push_str(&format_row(row.iter().map(|c| c.as_str()).collect()));
+        }
+        res
+    }
+
+    /// Writes the statistics in CSV format (one line per step, times in microseconds,
+    /// memory in bytes).
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "step,var,time_us,monomials,coefficients,max_degree,numer_bits,denom_bits,memory_bytes"
+        )?;
+        for s in &self.steps {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{}",
+                s.step,
+                s.var,
+                s.time.as_micros(),
+                s.poly.nb_monos,
+                s.poly.nb_coefs,
+                s.poly.max_degree,
+                s.poly.max_numer_bits,
+                s.poly.max_denom_bits,
+                s.poly.memory_bytes
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn save_csv(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Cannot create {path}: {e}"))?;
+        let mut out = BufWriter::new(file);
+        self.write_csv(&mut out)
+            .and_then(|_| out.flush())
+            .map_err(|e| format!("Cannot write {path}: {e}"))
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes}B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1}KiB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1}MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1}GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{PolyStats, RunStats, StepStats};
+
+    fn step(step: usize, nb_monos: usize, max_degree: i64) -> StepStats {
+        StepStats {
+            step,
+            var: format!("x{step}"),
+            time: Duration::from_micros(10),
+            poly: PolyStats {
+                nb_monos,
+                nb_coefs: 1,
+                max_degree,
+                max_numer_bits: 1,
+                max_denom_bits: 3,
+                memory_bytes: 100,
+            },
+        }
+    }
+
+    #[test]
+    fn test_peak_and_blowup() {
+        let mut run = RunStats::new();
+        run.push(step(1, 2, 1));
+        run.push(step(2, 7, 2));
+        run.push(step(3, 3, 4));
+        assert_eq!(run.peak.nb_monos, 7);
+        assert_eq!(run.peak.max_degree, 4);
+        assert_eq!(run.total_time, Duration::from_micros(30));
+        assert_eq!(run.blowup_step().unwrap().var, "x2");
+    }
+
+    #[test]
+    fn test_table_and_csv() {
+        let mut run = RunStats::new();
+        run.push(step(1, 2, 1));
+        run.push(step(2, 12, 2));
+        let table = run.to_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines.iter().all(|l| l.chars().count() == lines[0].chars().count()));
+        assert!(lines[4].trim_start().starts_with("peak"));
+
+        let mut csv = Vec::new();
+        run.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+        assert_eq!(csv.lines().nth(2).unwrap(), "2,x2,10,12,1,2,1,3,100");
+    }
+}
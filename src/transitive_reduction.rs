@@ -1,246 +1,134 @@
-use std::collections::{HashMap, VecDeque};
+// This module computes the transitive closure and the transitive reduction of the DAGs given
+// by their adjacency matrices, and the cover graph of a strict order. The closure is built in
+// reverse topological order, each node reaching its successors and all that they reach; an
+// edge `i -> j` is kept in the reduction when no other successor of `i` reaches `j`. The
+// matrices are converted to bit matrices, whose rows are combined a word at a time.
 
-/// Performs a topological reduction based on a given topological order of a graph.
-/// It identifies and marks the edges that can be removed to achieve transitive reduction.
+use crate::bitmatrix::BitMatrix;
+
+/// Sorts the nodes of a DAG topologically (Kahn's algorithm).
 ///
 /// # Arguments
 ///
-/// * `ordre_topologique` - A reference to a vector of tuples representing the topological order of the graph.
+/// * `matrice` - The adjacency matrix of the graph.
 ///
 /// # Returns
 ///
-/// A vector of tuples representing the edges to be removed to achieve a transitive reduction.
-pub fn transitive_reduction_topologique(ordre_topologique: &Vec<(usize, usize)>) -> Vec<(usize, usize)> {
-    let mut to_remove = Vec::new();
-    let n = ordre_topologique.len();
-    let mut mark = vec![false; n];
-
-    // Create a mapping from vertex to its index in the topological sort.
-    let vertex_to_index: HashMap<usize, usize> = ordre_topologique.iter().enumerate()
-        .map(|(i, &(src, _))| (src, i))
-        .collect();
-
-    for (i, &(_, target)) in ordre_topologique.iter().enumerate() {
-        for j in 0..i {
-            mark[j] = false;  // Reset marks for this run
-        }
-
-        // Mark predecessors
-        for j in 0..i {
-            let (source, intermediate) = ordre_topologique[j];
-            if intermediate == target && !mark[j] {
-                to_remove.push((source, target));  // Mark this edge for removal
-                mark[j] = true;
-            }
-        }
-    }
-
-    ordre_topologique.iter().cloned()
-        .filter(|&(src, tgt)| !to_remove.contains(&(src, tgt)))
-        .collect()
+/// The nodes, each one before its successors, or a node on a cycle if the graph has one.
+pub fn topological_order(matrice: &[Vec<i32>]) -> Result<Vec<usize>, usize> {
+    BitMatrix::from_matrice(matrice).topological_order()
 }
 
-/// Finds the largest index j for which the vertex connects to a specified vertex in the topological order.
+/// Computes the transitive closure of a DAG.
 ///
 /// # Arguments
 ///
-/// * `ordre_topologique` - A reference to the topological order of the graph.
-/// * `vertex` - The vertex for which to find the maximum index.
+/// * `matrice` - The adjacency matrix of the graph.
 ///
 /// # Returns
 ///
-/// The maximum index of vertices connecting to the specified vertex.
-fn trouver_jmax(ordre_topologique: &Vec<(usize, usize)>, vertex: usize) -> usize {
-    for (i, &item) in ordre_topologique.iter().enumerate().rev() {
-        if item.1 == vertex {
-            return item.0;
-        }
-    }
-    0
+/// The strict order of the DAG, `closure[i][j]` being true when there is a path from `i` to
+/// `j`, or a node on a cycle if the graph has one.
+pub fn transitive_closure(matrice: &[Vec<i32>]) -> Result<Vec<Vec<bool>>, usize> {
+    Ok(BitMatrix::from_matrice(matrice).transitive_closure()?.to_relation())
 }
 
-
-/// Applies the transitive reduction to a graph given the edges to remove.
+/// Computes the transitive reduction of a DAG: the edges which are the only path between
+/// their ends.
 ///
 /// # Arguments
 ///
-/// * `length` - The number of vertices in the graph.
-/// * `to_remove` - A vector of tuples representing edges to be removed.
+/// * `matrice` - The adjacency matrix of the graph.
 ///
 /// # Returns
 ///
-/// An adjacency matrix representing the graph after applying the transitive reduction.
-pub fn apply_transitive_reduction(length: usize, to_remove: Vec<(usize, usize)>) -> Vec<Vec<i32>> {
-    let mut matrice = vec![vec![0; length]; length];
-    for (source, target) in to_remove {
-        matrice[source][target] = 1;
-    }
-    matrice
+/// The adjacency matrix of the reduction, or a node on a cycle if the graph has one.
+pub fn transitive_reduction(matrice: &[Vec<i32>]) -> Result<Vec<Vec<i32>>, usize> {
+    Ok(BitMatrix::from_matrice(matrice).transitive_reduction()?.to_matrice())
 }
 
-/// Generates a topological order for a graph represented by an adjacency matrix.
+/// Computes the cover graph of a strict order given by its (transitive) relation matrix: `i`
+/// is covered by `j` when `i < j` with no element in between.
 ///
 /// # Arguments
 ///
-/// * `matrice` - An adjacency matrix of the graph.
+/// * `ordre` - The relation, `ordre[i][j]` being true when `i < j`.
 ///
 /// # Returns
 ///
-/// A vector of tuples representing the topological order of the graph.
-pub fn create_ordre_topologique(matrice: Vec<Vec<i32>>) -> Vec<(usize, usize)> {
-    let n = matrice.len();
-    let mut in_degree = vec![0; n];
-    let mut queue = VecDeque::new();
-    let mut ordre_topologique = Vec::new();
-
-    // Calculate in-degrees of each vertex.
-    for i in 0..n {
-        for j in 0..n {
-            if matrice[i][j] == 1 {
-                in_degree[j] += 1;
-            }
-        }
-    }
-
-    // Enqueue vertices with zero in-degree.
-    for i in 0..n {
-        if in_degree[i] == 0 {
-            queue.push_back(i);
-        }
-    }
+/// The adjacency matrix of the cover graph.
+pub fn covers(ordre: &[Vec<bool>]) -> Vec<Vec<i32>> {
+    BitMatrix::from_relation(ordre).covers().to_matrice()
+}
 
-    // Kahn's algorithm for topological sorting.
-    while let Some(i) = queue.pop_front() {
-        for j in 0..n {
-            if matrice[i][j] == 1 {
-                ordre_topologique.push((i, j));
-                in_degree[j] -= 1;
-                if in_degree[j] == 0 {
-                    queue.push_back(j);
+#[cfg(test)]
+mod tests {
+    use super::{covers, topological_order, transitive_closure, transitive_reduction};
+    use crate::generator_matrix::{erdos_renyi_generator, rng_from_seed};
+
+    /// Whether there is a path from `i` to `j` (of at least one edge), by depth-first search.
+    fn chemin(matrice: &[Vec<i32>], i: usize, j: usize) -> bool {
+        let mut vus = vec![false; matrice.len()];
+        let mut pile = vec![i];
+        while let Some(noeud) = pile.pop() {
+            for k in (0..matrice.len()).filter(|&k| matrice[noeud][k] == 1) {
+                if k == j {
+                    return true;
+                }
+                if !vus[k] {
+                    vus[k] = true;
+                    pile.push(k);
                 }
             }
         }
+        false
     }
 
-    ordre_topologique
-}
-
-
-
-
-/// Converts a topological order back to an adjacency matrix.
-///
-/// # Arguments
-///
-/// * `ordre_topologique` - A vector of tuples representing the topological order.
-/// * `nombre_noeuds` - The number of nodes in the graph.
-///
-/// # Returns
-///
-/// An adjacency matrix representing the graph based on the topological order.
-pub fn ordre_topologique_to_matrice_adj(ordre_topologique: Vec<(usize, usize)>, nombre_noeuds: usize) -> Vec<Vec<i32>> {
-    let mut matrice = vec![vec![0; nombre_noeuds]; nombre_noeuds];
-
-    for (source, target) in ordre_topologique {
-        if source < nombre_noeuds && target < nombre_noeuds {
-            matrice[source][target] = 1;
-        }
-    }
-
-    matrice
-}
-
-/// Retrieves a list of unique order indices from a list of tuples representing edges.
-///
-/// # Arguments
-///
-/// * `matrice` - A vector of tuples representing edges.
-///
-/// # Returns
-///
-/// A vector of unique vertex indices in their order of appearance.
-fn get_ordre(matrice: Vec<(usize, usize)>) -> Vec<usize> {
-    let mut order = Vec::new();
-    let mut cpt = 0;
-    for nombre in matrice  {
-        if cpt>0 {
-            if order[cpt-1]!= nombre.0 {
-                order.push(nombre.0);
-                cpt+=1;
+    #[test]
+    fn test_random_dags() {
+        for seed in 0..40 {
+            let p = [0.1, 0.3, 0.6, 0.9][seed as usize % 4];
+            let matrice = erdos_renyi_generator(12, p, &mut rng_from_seed(seed));
+            let n = matrice.len();
+            let closure = transitive_closure(&matrice).unwrap();
+            let reduction = transitive_reduction(&matrice).unwrap();
+            for i in 0..n {
+                for j in 0..n {
+                    assert_eq!(closure[i][j], chemin(&matrice, i, j), "seed {seed}: closure {i} {j}");
+                    // an edge is redundant when it is not the only path
+                    let mut sans_arete = matrice.clone();
+                    sans_arete[i][j] = 0;
+                    let redondante = chemin(&sans_arete, i, j);
+                    assert_eq!(reduction[i][j] == 1, matrice[i][j] == 1 && !redondante, "seed {seed}: reduction {i} {j}");
+                }
             }
-        }else{
-            order.push(nombre.0);
-            cpt+=1;
+            // the closure and the reduction have the same order
+            assert_eq!(transitive_closure(&reduction).unwrap(), closure);
+            assert_eq!(covers(&closure), reduction);
+            let ordre = topological_order(&matrice).unwrap();
+            let position: Vec<usize> = (0..n).map(|i| ordre.iter().position(|&k| k == i).unwrap()).collect();
+            assert!((0..n).all(|i| (0..n).all(|j| matrice[i][j] == 0 || position[i] < position[j])));
         }
-
     }
-     order
-}
 
-
-
-/// Checks if there is a direct edge from a specific vertex to another in the topological order.
-///
-/// # Arguments
-///
-/// * `ordre_topologique` - A reference to the topological order of the graph.
-/// * `vertex` - The starting vertex.
-///
-/// # Returns
-///
-/// `true` if there is a direct edge from the specified vertex, otherwise `false`.
-
-fn edge_to_vertex(ordre_topologique: &[(usize, usize)], vertex: usize) -> bool {
-    ordre_topologique.iter().any(|&(_, target)| target == vertex)
-}
-
-
-/// Checks if there is a direct connection from one vertex to another in the topological order.
-///
-/// # Arguments
-///
-/// * `ordre_topologique` - A reference to the vector of tuples representing the topological order.
-/// * `vertexj` - The starting vertex.
-/// * `vertexi` - The ending vertex.
-///
-/// # Returns
-///
-/// `true` if there is a direct connection from `vertexj` to `vertexi`, otherwise `false`.
-fn vertex_to_vertex (ordre_topologique: &Vec<(usize, usize)>,vertexj : usize,vertexi : usize) -> bool {
-    for i in 0..ordre_topologique.len(){
-        if ordre_topologique[i].0 == vertexj && ordre_topologique[i].1 == vertexi  {
-            return true
-        }
+    #[test]
+    fn test_long_paths() {
+        // 0 -> 1 -> ... -> 5 with all the shortcuts i -> j: only the path is left
+        let n = 6;
+        let matrice: Vec<Vec<i32>> = (0..n).map(|i| (0..n).map(|j| (i < j) as i32).collect()).collect();
+        let reduction = transitive_reduction(&matrice).unwrap();
+        let chaine: Vec<Vec<i32>> = (0..n).map(|i| (0..n).map(|j| (j == i + 1) as i32).collect()).collect();
+        assert_eq!(reduction, chaine);
+        assert_eq!(transitive_reduction(&chaine).unwrap(), chaine);
     }
-    false
-}
-
-/// Applies a Floyd-Warshall based transitive reduction algorithm on a given matrix.
-///
-/// # Arguments
-/// * `matrice` - A vector of vector of i32s representing the adjacency matrix to reduce.
-///
-/// # Returns
-/// The transitive reduced matrix.
-pub fn transitive_reduction_using_floyd_warshall(matrice: Vec<Vec<i32>>) -> Vec<Vec<i32>> {
-    let n = matrice.len();
-    let mut reduction = matrice.clone(); // Work on a clone to avoid altering the original during iteration
 
-    for k in 0..n {
-        for i in 0..n {
-            for j in 0..n {
-                if reduction[i][k] == 1 && reduction[k][j] == 1 {
-                    reduction[i][j] = 0; // Remove the direct edge if an indirect path exists
-                }
-            }
-        }
+    #[test]
+    fn test_cycles() {
+        // 0 -> 1 -> 2 -> 3 -> 1
+        let matrice = vec![vec![0, 1, 0, 0], vec![0, 0, 1, 0], vec![0, 0, 0, 1], vec![0, 1, 0, 0]];
+        assert!([1, 2, 3].contains(&topological_order(&matrice).unwrap_err()));
+        assert!(transitive_closure(&matrice).is_err());
+        assert_eq!(transitive_reduction(&[vec![1]]), Err(0));
+        assert_eq!(transitive_closure(&[]), Ok(vec![]));
     }
-
-    reduction
 }
-
-
-
-
-
-